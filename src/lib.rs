@@ -0,0 +1,7 @@
+//! N-puzzle library - grids, generators and solvers shared by the binary
+//! and the integration tests
+
+#[macro_use]
+extern crate clap;
+
+pub mod npuzzle;
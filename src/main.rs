@@ -1,29 +1,329 @@
-#[macro_use]
-extern crate clap;
-
 use log::*;
+use std::process;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use npuzzle::npuzzle::batch::{collect, Batch, Report};
+use npuzzle::npuzzle::bench::Bench;
+use npuzzle::npuzzle::enumerate::DistanceTable;
+use npuzzle::npuzzle::format::{compact_notation, Format, SolveReport};
+use npuzzle::npuzzle::grid2d::Grid2D;
+use npuzzle::npuzzle::grid_traits::Grid;
+use npuzzle::npuzzle::init_logger;
+use npuzzle::npuzzle::launcher::PuzzleMode;
+use npuzzle::npuzzle::optimal::OptimalSolutions;
+use npuzzle::npuzzle::optimize::optimize;
+use npuzzle::npuzzle::progress::{interrupt_flag, StatusLine};
+use npuzzle::npuzzle::replay::{parse_moves, Replay};
+use npuzzle::npuzzle::solver::Limits;
+use npuzzle::npuzzle::status::exit_code;
+use npuzzle::npuzzle::verify::Verifier;
+use npuzzle::npuzzle::Launcher;
+use npuzzle::npuzzle::{Astar, Board, Heuristic, Solver};
+use npuzzle::npuzzle::{ErrorKind, PuzzleError, Result};
+use npuzzle::npuzzle::{Score, ScoreBoard};
+
+const LEADERBOARD_LENGTH: usize = 10;
 
-mod npuzzle;
-use npuzzle::init_logger;
-use npuzzle::launcher::PuzzleMode;
-use npuzzle::Board;
-use npuzzle::Launcher;
-use npuzzle::{ErrorKind, PuzzleError, Result};
-
-fn main() -> Result<()> {
-	// Initialize logger - default to debug level, lets adjust it with arguments later
-	init_logger();
-	let launcher = Launcher::new();
+/// Largest board (in tiles) for which the optimal solution of a played game
+/// is computed, challenges are computed regardless
+const OPTIMAL_LIMIT: usize = 9;
+
+/// Search limits of the optimal solution of a played game, large challenges
+/// are shared without it
+const OPTIMAL_SEARCH: Limits = Limits {
+	timeout: Some(Duration::from_secs(5)),
+	max_nodes: None,
+	max_memory: Some(512 << 20),
+};
+
+fn main() {
+	if let Err(e) = run() {
+		debug!("{:?}", e);
+		eprintln!("{}", e.report());
+		process::exit(e.exit_code());
+	}
+}
+
+fn run() -> Result<()> {
+	let launcher = Launcher::new()?;
+	init_logger(launcher.get_log_config())?;
 	info!("{}", launcher);
-	let mut board: Board = launcher.get_board()?;
+	match launcher.get_mode() {
+		PuzzleMode::Solve => return solve_all(&launcher),
+		PuzzleMode::Batch => return batch(&launcher),
+		PuzzleMode::Bench => return bench(&launcher),
+		PuzzleMode::Analyze => return analyze(&launcher),
+		_ => {}
+	}
+	let mut board = launcher.get_board()?;
 	// consider using some kind of handler struct for this
 	// eg. Game::handle(board, mode)
 	match launcher.get_mode() {
-		PuzzleMode::Generate => println!("{}", board.as_output_string()),
-		PuzzleMode::Play => board.play(),
-		PuzzleMode::Solve => println!("Solver not implemented!"),
+		PuzzleMode::Generate => generate(&launcher, &board)?,
+		PuzzleMode::Play => play(&launcher, &mut board)?,
+		PuzzleMode::Replay => replay(&launcher, &board)?,
+		PuzzleMode::Verify => verify(&launcher, &board)?,
+		PuzzleMode::Optimal => optimal(&launcher, &board)?,
 		_ => return Err(PuzzleError::Puzzle(ErrorKind::InvalidMode)),
 	}
 
-	return Ok(());
+	Ok(())
+}
+
+fn generate(launcher: &Launcher, board: &Board) -> Result<()> {
+	match launcher.get_output_format() {
+		Format::Text => println!("{}", board.as_output_string()),
+		Format::Compact => println!("{}", board.as_compact_string()),
+		Format::Json => println!(
+			"{}",
+			serde_json::to_string(board).map_err(|e| PuzzleError::Custom(e.to_string()))?
+		),
+	}
+	Ok(())
+}
+
+fn play(launcher: &Launcher, board: &mut Board) -> Result<()> {
+	let initial = board.as_grid()?;
+	let start = Instant::now();
+	if let Some(moves) = board.play() {
+		let time = start.elapsed();
+		let (width, height) = board.get_size();
+		let challenge = launcher.get_challenge();
+		let optimal = if challenge.is_some() || width * height <= OPTIMAL_LIMIT {
+			optimal_length(initial, board.goal_grid()?)
+		} else {
+			None
+		};
+		let mut score = Score::new(board, launcher.get_seed(), moves, optimal, time);
+		score.challenge = challenge.map(|c| String::from(c.get_code()));
+		let mut scores = ScoreBoard::load(launcher.get_scores_file())?;
+		let rank = scores.add(score);
+		scores.save()?;
+		println!("Solved in {} moves, rank #{}\n", moves, rank);
+		println!("{}", scores.leaderboard(width, LEADERBOARD_LENGTH));
+		if let Some(challenge) = challenge {
+			println!(
+				"{}",
+				challenge.share_line(width, board.get_puzzletype(), moves, optimal)
+			);
+		}
+	}
+	Ok(())
+}
+
+fn optimal_length(start: Grid2D, goal: Grid2D) -> Option<usize> {
+	let mut astar = Astar::new(start, goal, Heuristic::LinearConflict);
+	astar.set_limits(OPTIMAL_SEARCH);
+	match astar.solve() {
+		Ok(solution) => Some(solution.len()),
+		Err(e) => {
+			warn!("Could not find optimal solution: {}", e);
+			None
+		}
+	}
+}
+
+/// Solves every puzzle of the input, a failing one does not stop the rest
+/// unless it was interrupted by Ctrl-C
+fn solve_all(launcher: &Launcher) -> Result<()> {
+	let boards = launcher.get_boards()?;
+	let interrupted = interrupt_flag()?;
+	let json = launcher.get_output_format() == Format::Json;
+	let total = boards.len();
+	let mut failure = None;
+	for (i, board) in boards.into_iter().enumerate() {
+		if total > 1 && !json {
+			println!(
+				"{}Puzzle {}/{}",
+				if i > 0 { "---\n" } else { "" },
+				i + 1,
+				total
+			);
+		}
+		if let Err(e) = board.and_then(|board| solve(launcher, &board, &interrupted)) {
+			if json {
+				println!("{}", SolveReport::<Grid2D>::error(&e).to_json()?);
+			}
+			if total == 1 || e.exit_code() == exit_code::INTERRUPTED {
+				return Err(e);
+			}
+			eprintln!("{}", e.report());
+			failure.get_or_insert(e.exit_code());
+		}
+	}
+	// errors were already reported, exit with the first one's code
+	if let Some(code) = failure {
+		process::exit(code);
+	}
+	Ok(())
+}
+
+fn solve(launcher: &Launcher, board: &Board, interrupted: &Arc<AtomicBool>) -> Result<()> {
+	let start = board.as_grid()?;
+	let mut solver = launcher.get_algorithm().solver(
+		start.clone(),
+		board.goal_grid()?,
+		launcher.get_heuristic(),
+		launcher.get_options(),
+	);
+	solver.set_limits(launcher.get_limits());
+	solver.set_observer(Box::new(StatusLine::new(Arc::clone(interrupted))));
+	let result = solver.solve();
+	// clears the status line before printing
+	drop(solver);
+	let mut solution = match result {
+		// json reports the partial result along with the error
+		Err(PuzzleError::Limit(partial)) if launcher.get_output_format() != Format::Json => {
+			println!("{}", partial);
+			return Err(PuzzleError::Limit(partial));
+		}
+		result => result?,
+	};
+	let mut savings = None;
+	if launcher.get_optimize() {
+		let (moves, saved) = optimize(&start, &solution.moves)?;
+		solution.moves = moves;
+		savings = Some(saved);
+	}
+	let states = solution.states(&start)?;
+	let verified = launcher.get_verify();
+	if verified {
+		let (width, height) = board.get_size();
+		Verifier::new(width, height, board.get_data(), board.get_puzzletype())
+			.verify(&solution, &states)?;
+	}
+	match launcher.get_output_format() {
+		format @ Format::Text | format @ Format::Compact => {
+			println!("{}", solution);
+			if let Some(savings) = savings {
+				println!("{}", savings);
+			}
+			println!("Sequence of states:");
+			let (width, height) = board.get_size();
+			for state in states.iter() {
+				if format == Format::Compact {
+					let tiles: Vec<usize> =
+						(0..width * height).map(|i| state.get_indexed(i)).collect();
+					println!("{}", compact_notation(width, &tiles));
+				} else {
+					println!("{}", state);
+				}
+			}
+			if verified {
+				println!("Solution verified");
+			}
+		}
+		Format::Json => {
+			let report = SolveReport::Solved {
+				puzzle: board,
+				solution: &solution,
+				states: &states,
+				verified,
+				optimized: savings.as_ref(),
+			};
+			println!("{}", report.to_json()?);
+		}
+	}
+	Ok(())
+}
+
+fn batch(launcher: &Launcher) -> Result<()> {
+	let jobs = collect(
+		launcher.get_batch_path(),
+		launcher.get_input_format(),
+		launcher.get_goal_override(),
+	)?;
+	let outcomes = Batch::new(
+		launcher.get_algorithm(),
+		launcher.get_heuristic(),
+		launcher.get_limits(),
+		launcher.get_jobs(),
+	)
+	.run(&jobs);
+	let report = Report::new(&outcomes);
+	if launcher.get_csv() {
+		print!("{}", report.to_csv());
+	} else {
+		println!("{}", report);
+	}
+	Ok(())
+}
+
+fn bench(launcher: &Launcher) -> Result<()> {
+	let jobs = launcher.get_bench_jobs()?;
+	let report = Bench::new(
+		launcher.get_algorithms().to_vec(),
+		launcher.get_heuristics().to_vec(),
+		launcher.get_repeat(),
+		launcher.get_limits(),
+	)
+	.run(&jobs);
+	if launcher.get_csv() {
+		print!("{}", report.to_csv());
+	} else if launcher.get_output_format() == Format::Json {
+		println!("{}", report.to_json()?);
+	} else {
+		println!("{}", report);
+	}
+	Ok(())
+}
+
+fn analyze(launcher: &Launcher) -> Result<()> {
+	let (width, height) = launcher.get_dimensions();
+	let goal = Board::with_type(width, height, launcher.get_goal()).goal_grid()?;
+	let table = DistanceTable::new(&goal)?;
+	match launcher.get_output_format() {
+		Format::Json => println!("{}", table.to_json()?),
+		_ => println!("{}", table),
+	}
+	Ok(())
+}
+
+fn optimal(launcher: &Launcher, board: &Board) -> Result<()> {
+	let mut search = OptimalSolutions::new(
+		board.as_grid()?,
+		board.goal_grid()?,
+		launcher.get_heuristic(),
+	);
+	search.set_limits(launcher.get_limits());
+	let optimal = match search.solve(launcher.get_list()) {
+		// the statistics and the first shortest solution if any, as `solve`
+		Err(err @ PuzzleError::Limit(_)) => {
+			match launcher.get_output_format() {
+				Format::Json => println!("{}", SolveReport::<Grid2D>::error(&err).to_json()?),
+				_ => {
+					if let PuzzleError::Limit(partial) = &err {
+						println!("{}", partial);
+					}
+				}
+			}
+			return Err(err);
+		}
+		result => result?,
+	};
+	match launcher.get_output_format() {
+		Format::Json => println!(
+			"{}",
+			serde_json::to_string(&optimal).map_err(|e| PuzzleError::Custom(e.to_string()))?
+		),
+		_ => println!("{}", optimal),
+	}
+	Ok(())
+}
+
+fn replay(launcher: &Launcher, board: &Board) -> Result<()> {
+	let moves = parse_moves(&launcher.get_moves()?)?;
+	let replay = Replay::run(&board.as_grid()?, &board.goal_grid()?, &moves);
+	println!("{}", replay);
+	replay.check()
+}
+
+fn verify(launcher: &Launcher, board: &Board) -> Result<()> {
+	let moves = parse_moves(&launcher.get_moves()?)?;
+	let (width, height) = board.get_size();
+	Verifier::new(width, height, board.get_data(), board.get_puzzletype()).verify_moves(&moves)?;
+	println!("Valid solution of {} moves", moves.len());
+	Ok(())
 }
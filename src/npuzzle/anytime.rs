@@ -0,0 +1,200 @@
+//! Anytime weighted A* for sliding puzzle
+//!
+//! Weighted A*, f = g + w * h, finds a solution at most w times longer than
+//! the shortest one, the faster the higher the weight. This solver runs it
+//! with the weights of `WEIGHTS`, from high to low, and reports every shorter
+//! solution to the observer with its bound. The last weight is 1, so given
+//! enough time it ends with a solution proven to be a shortest one. A limit stopping it after
+//! a first solution gives the best one so far.
+
+use std::cell::RefCell;
+use std::hash::Hash;
+use std::rc::Rc;
+use std::time::Instant;
+
+use super::astar::Astar;
+use super::grid2d::Direction;
+use super::grid_traits::Grid;
+use super::heuristic::Heuristic;
+use super::solver::{is_solvable, Limits, Observer, Progress, Solution, Solver};
+use super::{ErrorKind, PuzzleError, Result};
+
+/// Weights w as (g weight, h weight), each run is w = h weight / g weight
+pub const WEIGHTS: [(usize, usize); 6] = [(1, 5), (1, 3), (1, 2), (2, 3), (4, 5), (1, 1)];
+
+/// Observer of the whole search lent to each weighted run
+struct Shared(Rc<RefCell<Box<dyn Observer>>>);
+
+impl Observer for Shared {
+	fn progress(&mut self, progress: &Progress) -> bool {
+		self.0.borrow_mut().progress(progress)
+	}
+}
+
+pub struct Anytime<G: Grid> {
+	start: G,
+	destination: G,
+	heuristic: Heuristic,
+	time_complex: usize,
+	size_complex: usize,
+	limits: Limits,
+	observer: Option<Rc<RefCell<Box<dyn Observer>>>>,
+}
+
+impl<G> Anytime<G>
+where
+	G: Grid<M = Direction> + Clone + Hash + Eq,
+{
+	pub fn new(start: G, destination: G, heuristic: Heuristic) -> Self {
+		Self {
+			start,
+			destination,
+			heuristic,
+			time_complex: 0,
+			size_complex: 0,
+			limits: Limits::default(),
+			observer: None,
+		}
+	}
+
+	/// What is left of the limits after the runs so far
+	fn remaining(&self, timer: &Instant) -> Limits {
+		Limits {
+			timeout: self
+				.limits
+				.timeout
+				.map(|timeout| timeout.saturating_sub(timer.elapsed())),
+			max_nodes: self
+				.limits
+				.max_nodes
+				.map(|max| max.saturating_sub(self.time_complex)),
+			max_memory: self.limits.max_memory,
+		}
+	}
+}
+
+impl<G> Solver for Anytime<G>
+where
+	G: Grid<M = Direction> + Clone + Hash + Eq,
+{
+	/// Finds the shortest path, or the best one found when stopped by a limit
+	fn solve(&mut self) -> Result<Solution> {
+		let timer = Instant::now();
+		if !is_solvable(&self.start, &self.destination) {
+			return Err(PuzzleError::Puzzle(ErrorKind::Unsolvable));
+		}
+		self.time_complex = 0;
+		self.size_complex = 0;
+		let mut best: Option<Solution> = None;
+		for &(g_weight, h_weight) in WEIGHTS.iter() {
+			let mut astar =
+				Astar::new(self.start.clone(), self.destination.clone(), self.heuristic);
+			astar.set_weights(g_weight, h_weight);
+			astar.set_limits(self.remaining(&timer));
+			if let Some(observer) = &self.observer {
+				astar.set_observer(Box::new(Shared(Rc::clone(observer))));
+			}
+			let result = astar.solve();
+			self.time_complex += astar.get_time_complexity();
+			self.size_complex = self.size_complex.max(astar.get_size_complexity());
+			let solution = match (result, &best) {
+				(Ok(solution), _) => solution,
+				// the best solution so far is the answer
+				(Err(PuzzleError::Limit(_)), Some(_)) => break,
+				(Err(PuzzleError::Limit(mut partial)), None) => {
+					partial.time_complexity = self.time_complex;
+					partial.size_complexity = self.size_complex;
+					partial.duration = timer.elapsed();
+					return Err(PuzzleError::Limit(partial));
+				}
+				(Err(e), _) => return Err(e),
+			};
+			// the last run proves the optimality even of a solution as long
+			let optimal = g_weight == h_weight;
+			if optimal || best.as_ref().is_none_or(|best| solution.len() < best.len()) {
+				if let Some(observer) = &self.observer {
+					let bound = h_weight as f64 / g_weight as f64;
+					let found = Solution {
+						duration: timer.elapsed(),
+						..solution.clone()
+					};
+					observer.borrow_mut().improved(&found, bound);
+				}
+				best = Some(solution);
+			}
+		}
+		let mut solution = best.unwrap();
+		solution.time_complexity = self.time_complex;
+		solution.size_complexity = self.size_complex;
+		solution.duration = timer.elapsed();
+		Ok(solution)
+	}
+
+	fn set_limits(&mut self, limits: Limits) {
+		self.limits = limits;
+	}
+
+	fn set_observer(&mut self, observer: Box<dyn Observer>) {
+		self.observer = Some(Rc::new(RefCell::new(observer)));
+	}
+
+	fn get_time_complexity(&self) -> usize {
+		self.time_complex
+	}
+
+	fn get_size_complexity(&self) -> usize {
+		self.size_complex
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::bench::generate;
+	use super::super::PuzzleType;
+	use super::*;
+
+	/// Records the reported solutions
+	struct Recorder(Rc<RefCell<Vec<(usize, f64)>>>);
+
+	impl Observer for Recorder {
+		fn progress(&mut self, _progress: &Progress) -> bool {
+			true
+		}
+
+		fn improved(&mut self, solution: &Solution, bound: f64) {
+			self.0.borrow_mut().push((solution.len(), bound));
+		}
+	}
+
+	#[test]
+	fn improving_solutions() {
+		let job = generate(4, 1, 80, 3, PuzzleType::Snail).pop().unwrap();
+		let board = job.board.unwrap();
+		let (start, goal) = (board.as_grid().unwrap(), board.goal_grid().unwrap());
+		let optimal = Astar::new(start.clone(), goal.clone(), Heuristic::LinearConflict)
+			.solve()
+			.unwrap()
+			.len();
+		let found = Rc::new(RefCell::new(Vec::new()));
+		let mut anytime = Anytime::new(start.clone(), goal.clone(), Heuristic::LinearConflict);
+		anytime.set_observer(Box::new(Recorder(Rc::clone(&found))));
+		let solution = anytime.solve().unwrap();
+		assert_eq!(optimal, solution.len());
+		assert_eq!(goal, *solution.states(&start).unwrap().last().unwrap());
+		let found = found.borrow();
+		assert_eq!(Some(&(optimal, 1.0)), found.last());
+		for pair in found.windows(2) {
+			assert!(pair[0].0 >= pair[1].0 && pair[0].1 > pair[1].1);
+		}
+		for &(length, bound) in found.iter() {
+			assert!(length as f64 <= bound * optimal as f64);
+		}
+		// the first solution is kept when the node limit stops the later runs
+		let mut limited = Anytime::new(start, goal, Heuristic::LinearConflict);
+		limited.set_limits(Limits {
+			max_nodes: Some(solution.time_complexity / 2),
+			..Limits::default()
+		});
+		assert!(limited.solve().unwrap().len() >= optimal);
+	}
+}
@@ -1,20 +1,262 @@
 //! Generic implementation of A* for sliding puzzle
 //!
 //! Should do following:
-//! 	- generic heuristic
-//! 	- track complexity in time
-//! 	- track complexity in size
-//! 	- number of moves between states
-//! 	- final "path" / set of moves
-//! 	- solvability
+//!     - generic heuristic
+//!     - track complexity in time
+//!     - track complexity in size
+//!     - number of moves between states
+//!     - final "path" / set of moves
+//!     - solvability
 //!
 
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+use std::time::Instant;
+
+use super::grid2d::Direction;
 use super::grid_traits::Grid;
+use super::heuristic::{Evaluator, Heuristic};
+use super::solver::{is_solvable, state_bytes, Limits, Partial, Solution, Solver};
+use super::solver::{Observer, Progress, PROGRESS_INTERVAL};
+use super::{ErrorKind, PuzzleError, Result};
+
+/// Entry of the open set
+struct OpenNode<G> {
+	f: usize,
+	g: usize,
+	h: usize,
+	index: usize,
+	state: G,
+}
+
+/// Reversed ordering to turn max-heap into min-heap by f,
+/// ties are broken in favour of deeper nodes
+impl<G> Ord for OpenNode<G> {
+	fn cmp(&self, other: &Self) -> Ordering {
+		other.f.cmp(&self.f).then(self.g.cmp(&other.g))
+	}
+}
+
+impl<G> PartialOrd for OpenNode<G> {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl<G> PartialEq for OpenNode<G> {
+	fn eq(&self, other: &Self) -> bool {
+		self.f == other.f && self.g == other.g
+	}
+}
+
+impl<G> Eq for OpenNode<G> {}
 
 pub struct Astar<G: Grid> {
 	start: G,
 	destination: G,
+	evaluator: Evaluator,
 	time_complex: usize,
 	size_complex: usize,
-	path: Vec<G>,
+	limits: Limits,
+	observer: Option<Box<dyn Observer>>,
+	g_weight: usize,
+	h_weight: usize,
+}
+
+impl<G> Astar<G>
+where
+	G: Grid<M = Direction> + Clone + Hash + Eq,
+{
+	pub fn new(start: G, destination: G, heuristic: Heuristic) -> Self {
+		let evaluator = Evaluator::new(heuristic, &destination);
+		Self {
+			start,
+			destination,
+			evaluator,
+			time_complex: 0,
+			size_complex: 0,
+			limits: Limits::default(),
+			observer: None,
+			g_weight: 1,
+			h_weight: 1,
+		}
+	}
+
+	/// Orders the open set by f = g_weight * g + h_weight * h instead of g + h
+	///
+	/// (0, 1) gives greedy best-first search, (1, 0) uniform-cost search.
+	/// Only (1, 1) and (1, 0) guarantee the shortest path.
+	pub fn set_weights(&mut self, g_weight: usize, h_weight: usize) {
+		self.g_weight = g_weight;
+		self.h_weight = h_weight;
+	}
+
+	/// Open set entry of a state, h is estimated even if not weighted to
+	/// find the closest state reached
+	fn open_node(&self, g: usize, index: usize, state: G) -> OpenNode<G> {
+		let h = self.evaluator.estimate(&state);
+		OpenNode {
+			f: self.g_weight * g + self.h_weight * h,
+			g,
+			h,
+			index,
+			state,
+		}
+	}
+}
+
+impl<G> Solver for Astar<G>
+where
+	G: Grid<M = Direction> + Clone + Hash + Eq,
+{
+	/// Finds the shortest path from start to destination
+	///
+	/// Open set is a priority queue ordered by f = g + h, closed set is
+	/// a hash map of every seen state to its best known g and its index
+	/// in the parent table, stale queue entries are skipped on pop.
+	/// The expanded state of the lowest h is kept as the partial result.
+	fn solve(&mut self) -> Result<Solution> {
+		let timer = Instant::now();
+		if !is_solvable(&self.start, &self.destination) {
+			return Err(PuzzleError::Puzzle(ErrorKind::Unsolvable));
+		}
+		let mut open: BinaryHeap<OpenNode<G>> = BinaryHeap::new();
+		let mut closed: HashMap<G, (usize, usize)> = HashMap::new();
+		// (parent index, move leading here) of every seen state
+		let mut parents: Vec<Option<(usize, Direction)>> = vec![None];
+		closed.insert(self.start.clone(), (0, 0));
+		open.push(self.open_node(0, 0, self.start.clone()));
+		// (h, index) of the closest state expanded
+		let mut closest = (usize::MAX, 0);
+		let bytes = state_bytes(&self.start);
+		self.time_complex = 0;
+		self.size_complex = 1;
+		while let Some(node) = open.pop() {
+			if closed[&node.state].0 < node.g {
+				continue;
+			}
+			if node.state == self.destination {
+				return Ok(Solution {
+					moves: backtrack(&parents, node.index),
+					time_complexity: self.time_complex,
+					size_complexity: self.size_complex,
+					duration: timer.elapsed(),
+					thread_nodes: Vec::new(),
+				});
+			}
+			self.time_complex += 1;
+			if node.h < closest.0 {
+				closest = (node.h, node.index);
+			}
+			let memory = (closed.len() + open.len()) * bytes;
+			let mut stop = self.limits.exceeded(self.time_complex, memory, &timer);
+			if let Some(observer) = self.observer.as_mut() {
+				if stop.is_none() && self.time_complex.is_multiple_of(PROGRESS_INTERVAL) {
+					let progress = Progress {
+						nodes: self.time_complex,
+						open: open.len(),
+						closed: closed.len(),
+						bound: node.f,
+						best_h: closest.0,
+						elapsed: timer.elapsed(),
+					};
+					if !observer.progress(&progress) {
+						stop = Some(ErrorKind::Interrupted);
+					}
+				}
+			}
+			if let Some(limit) = stop {
+				return Err(PuzzleError::Limit(Box::new(Partial {
+					limit,
+					moves: backtrack(&parents, closest.1),
+					estimate: closest.0,
+					time_complexity: self.time_complex,
+					size_complexity: self.size_complex,
+					duration: timer.elapsed(),
+				})));
+			}
+			for dir in node.state.possible_moves() {
+				let mut child = node.state.clone();
+				child.do_move(&dir)?;
+				let g = node.g + 1;
+				if let Some(&(known, _)) = closed.get(&child) {
+					if known <= g {
+						continue;
+					}
+				}
+				parents.push(Some((node.index, dir)));
+				let index = parents.len() - 1;
+				open.push(self.open_node(g, index, child.clone()));
+				closed.insert(child, (g, index));
+			}
+			self.size_complex = self.size_complex.max(closed.len());
+		}
+		Err(PuzzleError::Puzzle(ErrorKind::Unsolvable))
+	}
+
+	fn set_limits(&mut self, limits: Limits) {
+		self.limits = limits;
+	}
+
+	fn set_observer(&mut self, observer: Box<dyn Observer>) {
+		self.observer = Some(observer);
+	}
+
+	fn get_time_complexity(&self) -> usize {
+		self.time_complex
+	}
+
+	fn get_size_complexity(&self) -> usize {
+		self.size_complex
+	}
+}
+
+/// Reconstructs moves leading to the node at given index
+pub fn backtrack(parents: &[Option<(usize, Direction)>], mut index: usize) -> Vec<Direction> {
+	let mut moves = Vec::new();
+	while let Some((parent, dir)) = parents[index] {
+		moves.push(dir);
+		index = parent;
+	}
+	moves.reverse();
+	moves
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::grid2d::Grid2D;
+	use super::super::PuzzleType;
+	use super::*;
+
+	fn grid(data: Vec<Vec<usize>>) -> Grid2D {
+		let mut grid = Grid2D::new();
+		grid.from_2dvec(data).unwrap();
+		grid
+	}
+
+	#[test]
+	fn subject_example() {
+		let start = grid(vec![vec![3, 2, 6], vec![1, 4, 0], vec![8, 7, 5]]);
+		let goal = grid(PuzzleType::Snail.get_template(3, 3));
+		let mut lengths = Vec::new();
+		for heuristic in [Heuristic::Manhattan, Heuristic::LinearConflict].iter() {
+			let mut astar = Astar::new(start.clone(), goal.clone(), *heuristic);
+			let solution = astar.solve().unwrap();
+			let states = solution.states(&start).unwrap();
+			assert_eq!(goal, *states.last().unwrap());
+			lengths.push(solution.len());
+		}
+		assert_eq!(lengths[0], lengths[1]);
+		let mut astar = Astar::new(goal.clone(), goal.clone(), Heuristic::Manhattan);
+		assert!(astar.solve().unwrap().is_empty());
+	}
+
+	#[test]
+	fn unsolvable() {
+		let start = grid(vec![vec![2, 1, 3], vec![8, 0, 4], vec![7, 6, 5]]);
+		let goal = grid(PuzzleType::Snail.get_template(3, 3));
+		let mut astar = Astar::new(start, goal, Heuristic::Manhattan);
+		assert!(astar.solve().is_err());
+	}
 }
@@ -0,0 +1,363 @@
+//! Solving many puzzles in one run - every file of a directory or every
+//! puzzle of a multi-puzzle file - with a summary report
+//!
+//! Puzzles are independent, so they are spread over worker threads, each
+//! of them running its own search within optional limits.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::format::Format;
+use super::heuristic::Heuristic;
+use super::solver::{state_bytes, Algorithm, Limits, Options};
+use super::utils::{format_csv, format_table};
+use super::{Board, ErrorKind, PuzzleError, PuzzleType, Result};
+
+/// Single puzzle of the batch, invalid ones are kept to be reported
+pub struct Job {
+	pub name: String,
+	pub board: Result<Board>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+	Solved,
+	Unsolvable,
+	Invalid,
+	Timeout,
+	/// Stopped by the node budget, the memory cap or another limit
+	Limit,
+	Error,
+}
+
+impl Status {
+	pub fn as_str(&self) -> &'static str {
+		match *self {
+			Self::Solved => "solved",
+			Self::Unsolvable => "unsolvable",
+			Self::Invalid => "invalid",
+			Self::Timeout => "timeout",
+			Self::Limit => "limit",
+			Self::Error => "error",
+		}
+	}
+
+	/// Whether the search was stopped by a limit
+	pub fn is_limit(&self) -> bool {
+		matches!(self, Self::Timeout | Self::Limit)
+	}
+
+	fn of(err: &PuzzleError) -> Self {
+		match err {
+			PuzzleError::Puzzle(ErrorKind::Unsolvable) => Self::Unsolvable,
+			PuzzleError::Limit(partial) if partial.limit == ErrorKind::Timeout => Self::Timeout,
+			PuzzleError::Limit(_) => Self::Limit,
+			PuzzleError::Parse(_) | PuzzleError::Io(_) => Self::Invalid,
+			err if err.exit_code() == super::status::exit_code::INVALID_INPUT => Self::Invalid,
+			_ => Self::Error,
+		}
+	}
+}
+
+impl fmt::Display for Status {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.as_str())
+	}
+}
+
+/// Result of a single job
+#[derive(Debug, Clone)]
+pub struct Outcome {
+	pub name: String,
+	pub status: Status,
+	/// Number of moves of the solution
+	pub length: Option<usize>,
+	/// States expanded
+	pub nodes: usize,
+	/// Peak memory held by the search in bytes, estimated by `state_bytes`
+	pub memory: usize,
+	pub time: Duration,
+	/// Error message of unsuccessful jobs
+	pub message: Option<String>,
+}
+
+impl Outcome {
+	fn fail(&mut self, err: &PuzzleError) {
+		self.status = Status::of(err);
+		// first line only, parse errors continue with the source excerpt
+		let report = err.report();
+		let first = report.lines().next().unwrap_or("");
+		self.message = Some(first.trim_start_matches("error: ").to_string());
+	}
+}
+
+/// Collects the jobs of a directory or a single file
+///
+/// Files of a directory are taken in alphabetical order, hidden files and
+/// subdirectories are skipped. Puzzles of a multi-puzzle file are named
+/// `file#1`, `file#2`, ... Each puzzle keeps its own goal unless `puzzletype`
+/// is given.
+pub fn collect(path: &str, format: Format, puzzletype: Option<PuzzleType>) -> Result<Vec<Job>> {
+	let files = if Path::new(path).is_dir() {
+		let mut files = Vec::new();
+		for entry in fs::read_dir(path)? {
+			let entry = entry?;
+			let hidden = entry.file_name().to_string_lossy().starts_with('.');
+			if entry.file_type()?.is_file() && !hidden {
+				files.push(entry.path().to_string_lossy().into_owned());
+			}
+		}
+		files.sort();
+		files
+	} else {
+		vec![String::from(path)]
+	};
+	let mut jobs = Vec::new();
+	for file in files {
+		match Board::all_from_file(&file, format) {
+			Ok(boards) => {
+				let several = boards.len() > 1;
+				for (i, mut board) in boards.into_iter().enumerate() {
+					if let (Ok(board), Some(puzzletype)) = (&mut board, puzzletype) {
+						board.set_puzzletype(puzzletype);
+					}
+					let name = match several {
+						true => format!("{}#{}", file, i + 1),
+						false => file.clone(),
+					};
+					jobs.push(Job { name, board });
+				}
+			}
+			Err(e) => jobs.push(Job {
+				name: file,
+				board: Err(e),
+			}),
+		}
+	}
+	Ok(jobs)
+}
+
+pub struct Batch {
+	algorithm: Algorithm,
+	heuristic: Heuristic,
+	limits: Limits,
+	threads: usize,
+}
+
+impl Batch {
+	pub fn new(algorithm: Algorithm, heuristic: Heuristic, limits: Limits, threads: usize) -> Self {
+		Self {
+			algorithm,
+			heuristic,
+			limits,
+			threads: threads.max(1),
+		}
+	}
+
+	/// Solves all the jobs, outcomes are in the order of the jobs
+	pub fn run(&self, jobs: &[Job]) -> Vec<Outcome> {
+		let next = AtomicUsize::new(0);
+		let outcomes: Mutex<Vec<Option<Outcome>>> = Mutex::new(vec![None; jobs.len()]);
+		thread::scope(|scope| {
+			for _ in 0..self.threads.min(jobs.len()) {
+				scope.spawn(|| loop {
+					let i = next.fetch_add(1, Ordering::Relaxed);
+					if i >= jobs.len() {
+						break;
+					}
+					let outcome = self.solve(&jobs[i]);
+					outcomes.lock().unwrap()[i] = Some(outcome);
+				});
+			}
+		});
+		outcomes
+			.into_inner()
+			.unwrap()
+			.into_iter()
+			.map(|outcome| outcome.unwrap())
+			.collect()
+	}
+
+	fn solve(&self, job: &Job) -> Outcome {
+		let timer = Instant::now();
+		let mut outcome = Outcome {
+			name: job.name.clone(),
+			status: Status::Solved,
+			length: None,
+			nodes: 0,
+			memory: 0,
+			time: Duration::from_secs(0),
+			message: None,
+		};
+		match job
+			.board
+			.as_ref()
+			.map(|board| self.search(board, &mut outcome))
+		{
+			Ok(Ok(length)) => outcome.length = Some(length),
+			Ok(Err(e)) => outcome.fail(&e),
+			Err(e) => outcome.fail(e),
+		}
+		outcome.time = timer.elapsed();
+		outcome
+	}
+
+	/// Length of the solution, statistics are recorded even on failure
+	fn search(&self, board: &Board, outcome: &mut Outcome) -> Result<usize> {
+		let start = board.as_grid()?;
+		let bytes = state_bytes(&start);
+		let mut solver = self.algorithm.solver(
+			start,
+			board.goal_grid()?,
+			self.heuristic,
+			Options::default(),
+		);
+		solver.set_limits(self.limits);
+		let solution = solver.solve();
+		outcome.nodes = solver.get_time_complexity();
+		outcome.memory = solver.get_size_complexity() * bytes;
+		Ok(solution?.len())
+	}
+}
+
+/// Table or CSV of the outcomes with a summary
+pub struct Report<'a> {
+	outcomes: &'a [Outcome],
+}
+
+impl<'a> Report<'a> {
+	pub fn new(outcomes: &'a [Outcome]) -> Self {
+		Self { outcomes }
+	}
+
+	fn rows(&self) -> Vec<Vec<String>> {
+		self.outcomes
+			.iter()
+			.map(|o| {
+				vec![
+					o.name.clone(),
+					o.status.to_string(),
+					o.length.map_or(String::from("-"), |l| l.to_string()),
+					o.nodes.to_string(),
+					o.memory.to_string(),
+					format!("{:.3}", o.time.as_secs_f64()),
+				]
+			})
+			.collect()
+	}
+
+	/// Comma separated values with a header line, names are quoted if needed
+	pub fn to_csv(&self) -> String {
+		let header = [
+			"file",
+			"status",
+			"length",
+			"nodes",
+			"memory_bytes",
+			"time_s",
+		];
+		format_csv(&header, &self.rows())
+	}
+
+	/// Count of each status, e.g. "4 puzzles: 3 solved, 1 unsolvable"
+	pub fn summary(&self) -> String {
+		let statuses = [
+			Status::Solved,
+			Status::Unsolvable,
+			Status::Invalid,
+			Status::Timeout,
+			Status::Limit,
+			Status::Error,
+		];
+		let counts: Vec<String> = statuses
+			.iter()
+			.map(|&s| (s, self.outcomes.iter().filter(|o| o.status == s).count()))
+			.filter(|&(_, count)| count > 0)
+			.map(|(s, count)| format!("{} {}", count, s))
+			.collect();
+		format!("{} puzzles: {}", self.outcomes.len(), counts.join(", "))
+	}
+}
+
+impl<'a> fmt::Display for Report<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let header = [
+			"file",
+			"status",
+			"length",
+			"nodes",
+			"memory (B)",
+			"time (s)",
+		];
+		// names and statuses to the left, numbers to the right
+		writeln!(f, "{}", format_table(&header, &self.rows(), 2))?;
+		for o in self.outcomes.iter() {
+			if let Some(message) = &o.message {
+				writeln!(f, "{}: {}", o.name, message)?;
+			}
+		}
+		write!(f, "{}", self.summary())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn batch_report() {
+		let dir = format!("{}/tests/npuzzle_states/basic", env!("CARGO_MANIFEST_DIR"));
+		let mut jobs = collect(&dir, Format::Text, None).unwrap();
+		assert_eq!(3, jobs.len());
+		jobs.push(Job {
+			name: String::from("broken"),
+			board: "3\n1 2 3\n".parse(),
+		});
+		let outcomes =
+			Batch::new(Algorithm::Astar, Heuristic::Manhattan, Limits::default(), 2).run(&jobs);
+		assert_eq!(4, outcomes.len());
+		assert!(outcomes[0].name.ends_with("00_subject"));
+		assert_eq!(Status::Invalid, outcomes[3].status);
+		assert!(outcomes[..3]
+			.iter()
+			.all(|o| o.status != Status::Invalid && o.status != Status::Error));
+		let report = Report::new(&outcomes);
+		assert!(report.summary().starts_with("4 puzzles: "));
+		assert_eq!(5, report.to_csv().lines().count());
+	}
+
+	#[test]
+	fn limit_statuses() {
+		let mut board: Board = "14 13 15 7 11 12 9 5 6 0 2 1 4 8 10 3".parse().unwrap();
+		board.set_puzzletype(PuzzleType::Lines00);
+		let bytes = state_bytes(&board.as_grid().unwrap());
+		let jobs = vec![Job {
+			name: String::from("korf1"),
+			board: Ok(board),
+		}];
+		let timeout = Limits {
+			timeout: Some(Duration::from_secs(0)),
+			..Limits::default()
+		};
+		let outcome = &Batch::new(Algorithm::Astar, Heuristic::Manhattan, timeout, 1).run(&jobs)[0];
+		assert_eq!(Status::Timeout, outcome.status);
+		// the states held, each of the estimated size
+		assert!(outcome.memory > 0 && outcome.memory.is_multiple_of(bytes));
+		let nodes = Limits {
+			max_nodes: Some(10),
+			..Limits::default()
+		};
+		let outcome = &Batch::new(Algorithm::Astar, Heuristic::Manhattan, nodes, 1).run(&jobs)[0];
+		assert_eq!(Status::Limit, outcome.status);
+		let report = Report::new(std::slice::from_ref(outcome));
+		assert_eq!("1 puzzles: 1 limit", report.summary());
+		assert!(report
+			.to_csv()
+			.starts_with("file,status,length,nodes,memory_bytes,time_s\n"));
+	}
+}
@@ -0,0 +1,224 @@
+//! Beam search for sliding puzzle
+//!
+//! Breadth-first search keeping only the `width` states of the lowest
+//! heuristic estimate at each depth, the others are dropped for good. It
+//! reaches boards far beyond the exact searches in time and memory linear
+//! in the width and the depth, with no guarantee on the length of the
+//! solution nor to find one at all: the beam may run out of new states, the
+//! search then fails with `ErrorKind::BeamExhausted` and the path to the
+//! closest state reached. A wider beam is slower but fails less often and
+//! finds shorter solutions.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::time::Instant;
+
+use super::astar::backtrack;
+use super::grid2d::Direction;
+use super::grid_traits::Grid;
+use super::heuristic::{Evaluator, Heuristic};
+use super::solver::{is_solvable, state_bytes, Limits, Partial, Solution, Solver};
+use super::solver::{Observer, Progress, PROGRESS_INTERVAL};
+use super::{ErrorKind, PuzzleError, Result};
+
+/// States kept at each depth unless set otherwise
+pub const DEFAULT_BEAM_WIDTH: usize = 500;
+
+/// State of the beam with its estimate and index in the parent table
+struct Candidate<G> {
+	h: usize,
+	index: usize,
+	state: G,
+}
+
+pub struct BeamSearch<G: Grid> {
+	start: G,
+	destination: G,
+	evaluator: Evaluator,
+	width: usize,
+	time_complex: usize,
+	size_complex: usize,
+	limits: Limits,
+	observer: Option<Box<dyn Observer>>,
+}
+
+impl<G> BeamSearch<G>
+where
+	G: Grid<M = Direction> + Clone + Hash + Eq,
+{
+	pub fn new(start: G, destination: G, heuristic: Heuristic) -> Self {
+		let evaluator = Evaluator::new(heuristic, &destination);
+		Self {
+			start,
+			destination,
+			evaluator,
+			width: DEFAULT_BEAM_WIDTH,
+			time_complex: 0,
+			size_complex: 0,
+			limits: Limits::default(),
+			observer: None,
+		}
+	}
+
+	/// States kept at each depth
+	pub fn set_beam_width(&mut self, width: usize) {
+		self.width = width;
+	}
+}
+
+impl<G> Solver for BeamSearch<G>
+where
+	G: Grid<M = Direction> + Clone + Hash + Eq,
+{
+	/// Finds a path depth by depth, the first one reaching the goal
+	///
+	/// Every state seen is remembered so the beam never goes back to one,
+	/// the parent table gives the path as for A*.
+	fn solve(&mut self) -> Result<Solution> {
+		let timer = Instant::now();
+		if !is_solvable(&self.start, &self.destination) {
+			return Err(PuzzleError::Puzzle(ErrorKind::Unsolvable));
+		}
+		let mut seen: HashSet<G> = HashSet::new();
+		// (parent index, move leading here) of every state of the beams
+		let mut parents: Vec<Option<(usize, Direction)>> = vec![None];
+		seen.insert(self.start.clone());
+		let mut beam = vec![Candidate {
+			h: self.evaluator.estimate(&self.start),
+			index: 0,
+			state: self.start.clone(),
+		}];
+		// (h, index) of the closest state expanded
+		let mut closest = (usize::MAX, 0);
+		let bytes = state_bytes(&self.start);
+		let mut depth = 0;
+		self.time_complex = 0;
+		self.size_complex = 1;
+		while !beam.is_empty() {
+			let mut next: Vec<Candidate<G>> = Vec::with_capacity(beam.len() * 3);
+			for node in beam.iter() {
+				if node.state == self.destination {
+					return Ok(Solution {
+						moves: backtrack(&parents, node.index),
+						time_complexity: self.time_complex,
+						size_complexity: self.size_complex,
+						duration: timer.elapsed(),
+						thread_nodes: Vec::new(),
+					});
+				}
+				self.time_complex += 1;
+				if node.h < closest.0 {
+					closest = (node.h, node.index);
+				}
+				let memory = (seen.len() + next.len()) * bytes;
+				let mut stop = self.limits.exceeded(self.time_complex, memory, &timer);
+				if let Some(observer) = self.observer.as_mut() {
+					if stop.is_none() && self.time_complex.is_multiple_of(PROGRESS_INTERVAL) {
+						let progress = Progress {
+							nodes: self.time_complex,
+							open: beam.len(),
+							closed: seen.len(),
+							bound: depth,
+							best_h: closest.0,
+							elapsed: timer.elapsed(),
+						};
+						if !observer.progress(&progress) {
+							stop = Some(ErrorKind::Interrupted);
+						}
+					}
+				}
+				if let Some(limit) = stop {
+					return Err(self.partial(limit, &parents, closest, &timer));
+				}
+				for dir in node.state.possible_moves() {
+					let mut child = node.state.clone();
+					child.do_move(&dir)?;
+					if seen.contains(&child) {
+						continue;
+					}
+					parents.push(Some((node.index, dir)));
+					seen.insert(child.clone());
+					next.push(Candidate {
+						h: self.evaluator.estimate(&child),
+						index: parents.len() - 1,
+						state: child,
+					});
+				}
+			}
+			self.size_complex = self.size_complex.max(seen.len());
+			// stable, ties keep the order of generation
+			next.sort_by_key(|candidate| candidate.h);
+			next.truncate(self.width);
+			beam = next;
+			depth += 1;
+		}
+		Err(self.partial(ErrorKind::BeamExhausted, &parents, closest, &timer))
+	}
+
+	fn set_limits(&mut self, limits: Limits) {
+		self.limits = limits;
+	}
+
+	fn set_observer(&mut self, observer: Box<dyn Observer>) {
+		self.observer = Some(observer);
+	}
+
+	fn get_time_complexity(&self) -> usize {
+		self.time_complex
+	}
+
+	fn get_size_complexity(&self) -> usize {
+		self.size_complex
+	}
+}
+
+impl<G: Grid> BeamSearch<G> {
+	/// Search stopped with the path to the closest state
+	fn partial(
+		&self,
+		limit: ErrorKind,
+		parents: &[Option<(usize, Direction)>],
+		closest: (usize, usize),
+		timer: &Instant,
+	) -> PuzzleError {
+		PuzzleError::Limit(Box::new(Partial {
+			limit,
+			moves: backtrack(parents, closest.1),
+			estimate: closest.0,
+			time_complexity: self.time_complex,
+			size_complexity: self.size_complex,
+			duration: timer.elapsed(),
+		}))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::bench::generate;
+	use super::super::PuzzleType;
+	use super::*;
+
+	#[test]
+	fn beam_widths() {
+		let job = generate(6, 1, 400, 5, PuzzleType::Snail).pop().unwrap();
+		let board = job.board.unwrap();
+		let (start, goal) = (board.as_grid().unwrap(), board.goal_grid().unwrap());
+		let mut beam = BeamSearch::new(start.clone(), goal.clone(), Heuristic::LinearConflict);
+		beam.set_beam_width(200);
+		let solution = beam.solve().unwrap();
+		assert_eq!(goal, *solution.states(&start).unwrap().last().unwrap());
+		// a single state runs into a dead end sooner or later
+		let mut narrow = BeamSearch::new(start.clone(), goal, Heuristic::Hamming);
+		narrow.set_beam_width(1);
+		match narrow.solve() {
+			Err(PuzzleError::Limit(partial)) => {
+				assert_eq!(ErrorKind::BeamExhausted, partial.limit);
+				assert!(partial.estimate > 0);
+			}
+			other => panic!(
+				"expected the beam to fail, got {:?}",
+				other.map(|s| s.len())
+			),
+		}
+	}
+}
@@ -0,0 +1,363 @@
+//! Benchmark of the algorithms and heuristics over a set of puzzles
+//!
+//! Every selected algorithm solves every puzzle of the set with every
+//! selected heuristic, repeated a given number of times. For each pair it
+//! reports the time taken, states expanded, effective branching factor and
+//! optimality gap - how much longer the solutions are than the shortest
+//! ones. Statistics are taken over the solved runs only.
+//!
+//! The set is either generated from a seed, so the same seed gives the same
+//! puzzles on every run, or read from files as in the batch mode.
+
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Serialize;
+use std::fmt;
+
+use super::astar::Astar;
+use super::batch::{Batch, Job, Outcome, Status};
+use super::heuristic::Heuristic;
+use super::solver::{Algorithm, Limits, Solver};
+use super::utils::{format_csv, format_table};
+use super::{Board, PuzzleError, PuzzleType, Result};
+
+/// Puzzles shuffled from the goal by a generator seeded with given seed
+pub fn generate(
+	size: usize,
+	count: usize,
+	iterations: usize,
+	seed: u64,
+	puzzletype: PuzzleType,
+) -> Vec<Job> {
+	let mut rng = StdRng::seed_from_u64(seed);
+	(0..count)
+		.map(|i| {
+			let mut board = Board::with_type(size, size, puzzletype);
+			board.shuffle(iterations, &mut rng);
+			Job {
+				name: format!("seed {} #{}", seed, i + 1),
+				board: Ok(board),
+			}
+		})
+		.collect()
+}
+
+pub struct Bench {
+	algorithms: Vec<Algorithm>,
+	heuristics: Vec<Heuristic>,
+	repeat: usize,
+	limits: Limits,
+}
+
+impl Bench {
+	pub fn new(
+		algorithms: Vec<Algorithm>,
+		heuristics: Vec<Heuristic>,
+		repeat: usize,
+		limits: Limits,
+	) -> Self {
+		Self {
+			algorithms,
+			heuristics,
+			repeat: repeat.max(1),
+			limits,
+		}
+	}
+
+	/// Runs the whole matrix, one thread at a time not to skew the timing
+	pub fn run(&self, jobs: &[Job]) -> BenchReport {
+		let mut runs: Vec<(Algorithm, Heuristic, Vec<Outcome>)> = Vec::new();
+		for &algorithm in self.algorithms.iter() {
+			for &heuristic in self.heuristics.iter() {
+				let batch = Batch::new(algorithm, heuristic, self.limits, 1);
+				let mut outcomes = Vec::new();
+				for _ in 0..self.repeat {
+					outcomes.extend(batch.run(jobs));
+				}
+				runs.push((algorithm, heuristic, outcomes));
+			}
+		}
+		let optimal = self.optimal_lengths(jobs, &runs);
+		let cells = runs
+			.iter()
+			.map(|(algorithm, heuristic, outcomes)| {
+				Cell::new(*algorithm, *heuristic, outcomes, &optimal)
+			})
+			.collect();
+		BenchReport {
+			puzzles: jobs.len(),
+			repeat: self.repeat,
+			cells,
+		}
+	}
+
+	/// Shortest solution length of every job
+	///
+	/// All the heuristics are admissible, so any run of an optimal algorithm
+	/// gives it. Puzzles no such run solved are solved by A* with linear
+	/// conflict, within the same limits.
+	fn optimal_lengths(
+		&self,
+		jobs: &[Job],
+		runs: &[(Algorithm, Heuristic, Vec<Outcome>)],
+	) -> Vec<Option<usize>> {
+		let mut optimal = vec![None; jobs.len()];
+		for (algorithm, _, outcomes) in runs.iter() {
+			if algorithm.is_optimal() {
+				for (i, outcome) in outcomes.iter().enumerate() {
+					if outcome.length.is_some() {
+						optimal[i % jobs.len()] = outcome.length;
+					}
+				}
+			}
+		}
+		for (length, job) in optimal.iter_mut().zip(jobs.iter()) {
+			if length.is_none() {
+				if let Ok(board) = &job.board {
+					*length = self.solve_optimally(board).ok();
+				}
+			}
+		}
+		optimal
+	}
+
+	fn solve_optimally(&self, board: &Board) -> Result<usize> {
+		let mut astar = Astar::new(
+			board.as_grid()?,
+			board.goal_grid()?,
+			Heuristic::LinearConflict,
+		);
+		astar.set_limits(self.limits);
+		Ok(astar.solve()?.len())
+	}
+}
+
+/// Statistics of one algorithm with one heuristic over all the runs
+#[derive(Debug, Clone, Serialize)]
+pub struct Cell {
+	pub algorithm: Algorithm,
+	pub heuristic: Heuristic,
+	pub runs: usize,
+	pub solved: usize,
+	/// Runs stopped by the timeout or another limit
+	pub limited: usize,
+	pub time_mean_ms: f64,
+	pub time_median_ms: f64,
+	pub time_max_ms: f64,
+	pub nodes_mean: f64,
+	pub nodes_max: usize,
+	/// Mean effective branching factor
+	pub branching_factor: Option<f64>,
+	/// Mean and maximum excess length over the shortest solution, in percent
+	pub gap_mean_pct: Option<f64>,
+	pub gap_max_pct: Option<f64>,
+}
+
+impl Cell {
+	fn new(
+		algorithm: Algorithm,
+		heuristic: Heuristic,
+		outcomes: &[Outcome],
+		optimal: &[Option<usize>],
+	) -> Self {
+		let solved: Vec<(usize, &Outcome)> = outcomes
+			.iter()
+			.enumerate()
+			.filter(|(_, o)| o.status == Status::Solved)
+			.map(|(i, o)| (i % optimal.len(), o))
+			.collect();
+		let mut times: Vec<f64> = solved
+			.iter()
+			.map(|(_, o)| o.time.as_secs_f64() * 1000.0)
+			.collect();
+		times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		let nodes: Vec<f64> = solved.iter().map(|(_, o)| o.nodes as f64).collect();
+		let branching: Vec<f64> = solved
+			.iter()
+			.filter_map(|(_, o)| effective_branching_factor(o.nodes, o.length.unwrap()))
+			.collect();
+		let gaps: Vec<f64> = solved
+			.iter()
+			.filter_map(|&(i, o)| match optimal[i] {
+				Some(0) | None => None,
+				Some(best) => Some(100.0 * (o.length.unwrap() - best) as f64 / best as f64),
+			})
+			.collect();
+		Self {
+			algorithm,
+			heuristic,
+			runs: outcomes.len(),
+			solved: solved.len(),
+			limited: outcomes.iter().filter(|o| o.status.is_limit()).count(),
+			time_mean_ms: mean(&times).unwrap_or(0.0),
+			time_median_ms: median(&times).unwrap_or(0.0),
+			time_max_ms: times.last().copied().unwrap_or(0.0),
+			nodes_mean: mean(&nodes).unwrap_or(0.0),
+			nodes_max: solved.iter().map(|(_, o)| o.nodes).max().unwrap_or(0),
+			branching_factor: mean(&branching),
+			gap_mean_pct: mean(&gaps),
+			gap_max_pct: gaps.iter().copied().reduce(f64::max),
+		}
+	}
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+	match values.len() {
+		0 => None,
+		n => Some(values.iter().sum::<f64>() / n as f64),
+	}
+}
+
+/// Median of sorted values
+fn median(values: &[f64]) -> Option<f64> {
+	match values.len() {
+		0 => None,
+		n if n % 2 == 1 => Some(values[n / 2]),
+		n => Some((values[n / 2 - 1] + values[n / 2]) / 2.0),
+	}
+}
+
+/// Branching factor b of the uniform tree of given depth holding given
+/// number of nodes besides its root: nodes = b + b^2 + ... + b^depth
+///
+/// Solved numerically by bisection, undefined for the solved state.
+pub fn effective_branching_factor(nodes: usize, depth: usize) -> Option<f64> {
+	if depth == 0 {
+		return None;
+	}
+	let tree = |b: f64| (1..=depth as i32).map(|d| b.powi(d)).sum::<f64>();
+	let target = nodes as f64;
+	let (mut low, mut high) = (0.0, target.max(1.0));
+	for _ in 0..100 {
+		let middle = (low + high) / 2.0;
+		match tree(middle) < target {
+			true => low = middle,
+			false => high = middle,
+		}
+	}
+	Some((low + high) / 2.0)
+}
+
+/// Results of the whole matrix as a table, CSV or JSON
+#[derive(Debug, Serialize)]
+pub struct BenchReport {
+	pub puzzles: usize,
+	pub repeat: usize,
+	pub cells: Vec<Cell>,
+}
+
+impl BenchReport {
+	fn rows(&self) -> Vec<Vec<String>> {
+		let optional = |value: Option<f64>, precision: usize| {
+			value.map_or(String::from("-"), |v| {
+				format!("{:.precision$}", v, precision = precision)
+			})
+		};
+		self.cells
+			.iter()
+			.map(|c| {
+				vec![
+					c.algorithm.to_string(),
+					c.heuristic.to_string(),
+					c.runs.to_string(),
+					c.solved.to_string(),
+					c.limited.to_string(),
+					format!("{:.3}", c.time_mean_ms),
+					format!("{:.3}", c.time_median_ms),
+					format!("{:.3}", c.time_max_ms),
+					format!("{:.1}", c.nodes_mean),
+					c.nodes_max.to_string(),
+					optional(c.branching_factor, 3),
+					optional(c.gap_mean_pct, 1),
+					optional(c.gap_max_pct, 1),
+				]
+			})
+			.collect()
+	}
+
+	pub fn to_csv(&self) -> String {
+		let header = [
+			"algorithm",
+			"heuristic",
+			"runs",
+			"solved",
+			"limited",
+			"time_mean_ms",
+			"time_median_ms",
+			"time_max_ms",
+			"nodes_mean",
+			"nodes_max",
+			"branching_factor",
+			"gap_mean_pct",
+			"gap_max_pct",
+		];
+		format_csv(&header, &self.rows())
+	}
+
+	pub fn to_json(&self) -> Result<String> {
+		serde_json::to_string_pretty(self).map_err(|e| PuzzleError::Custom(e.to_string()))
+	}
+}
+
+impl fmt::Display for BenchReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let header = [
+			"algorithm",
+			"heuristic",
+			"runs",
+			"solved",
+			"limited",
+			"mean (ms)",
+			"median (ms)",
+			"max (ms)",
+			"nodes",
+			"max nodes",
+			"b*",
+			"gap %",
+			"max gap %",
+		];
+		writeln!(f, "{}", format_table(&header, &self.rows(), 2))?;
+		write!(
+			f,
+			"{} puzzles, {} run{} each",
+			self.puzzles,
+			self.repeat,
+			if self.repeat > 1 { "s" } else { "" }
+		)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn bench_matrix() {
+		let b = effective_branching_factor(2 + 4 + 8, 3).unwrap();
+		assert!((b - 2.0).abs() < 1e-9);
+		assert_eq!(None, effective_branching_factor(5, 0));
+		let jobs = generate(3, 4, 30, 7, PuzzleType::Snail);
+		let same = generate(3, 4, 30, 7, PuzzleType::Snail);
+		for (job, other) in jobs.iter().zip(same.iter()) {
+			assert_eq!(
+				job.board.as_ref().unwrap().get_data(),
+				other.board.as_ref().unwrap().get_data()
+			);
+		}
+		let algorithms = vec![Algorithm::Astar, Algorithm::Greedy];
+		let heuristics = vec![Heuristic::Manhattan, Heuristic::LinearConflict];
+		let report = Bench::new(algorithms, heuristics, 2, Limits::default()).run(&jobs);
+		assert_eq!(4, report.cells.len());
+		for cell in report.cells.iter() {
+			assert_eq!((8, 8), (cell.runs, cell.solved));
+			assert!(cell.time_median_ms <= cell.time_max_ms);
+			if cell.algorithm.is_optimal() {
+				assert_eq!(Some(0.0), cell.gap_max_pct);
+			}
+		}
+		assert_eq!(5, report.to_csv().lines().count());
+		assert!(report
+			.to_json()
+			.unwrap()
+			.contains("\"algorithm\": \"greedy\""));
+	}
+}
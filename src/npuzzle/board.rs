@@ -1,13 +1,22 @@
+use super::format::{compact_notation, Format};
+use super::grid2d::Grid2D;
+use super::grid_traits::Grid;
 use super::utils::is_in_bounds;
-use super::{ErrorKind, PuzzleError, Result};
+use super::{ErrorKind, ParseError, PuzzleError, PuzzleType, Result};
 use log::*;
-use rand::{seq::SliceRandom, thread_rng};
+use rand::{seq::SliceRandom, Rng};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashSet;
 use std::fmt;
 use std::fs::File;
+use std::io;
 use std::io::prelude::*;
+use std::str::FromStr;
 use text_io::read;
 
+/// Largest size of a puzzle read, bounding what a size header may allocate
+pub const MAX_SIZE: usize = 1000;
+
 #[derive(Debug, Hash)]
 pub struct Board {
 	width: usize,
@@ -15,22 +24,67 @@ pub struct Board {
 	data: Vec<usize>,
 	solution: Vec<usize>,
 	zero_position: usize,
+	puzzletype: PuzzleType,
 }
 
 impl Board {
 	pub fn new(width: usize, height: usize) -> Self {
 		let mut new_board = Self {
-			width: width,
-			height: height,
+			width,
+			height,
 			data: vec![0; width * height],
 			solution: vec![0; width * height],
 			zero_position: 0,
+			puzzletype: PuzzleType::Snail,
 		};
 		new_board.fill_board();
 		new_board.solution = new_board.data.clone();
 		new_board
 	}
 
+	/// Creates a solved board for the given goal type
+	pub fn with_type(width: usize, height: usize, puzzletype: PuzzleType) -> Self {
+		let mut new_board = Board::new(width, height);
+		new_board.set_puzzletype(puzzletype);
+		new_board.data = new_board.solution.clone();
+		new_board.zero_position = new_board.data.iter().position(|&x| x == 0).unwrap();
+		new_board
+	}
+
+	/// Changes the expected solution, current state is kept as is
+	pub fn set_puzzletype(&mut self, puzzletype: PuzzleType) {
+		self.puzzletype = puzzletype;
+		self.solution = puzzletype.get_template(self.width, self.height).concat();
+	}
+
+	pub fn get_puzzletype(&self) -> PuzzleType {
+		self.puzzletype
+	}
+
+	/// Returns (width, height)
+	pub fn get_size(&self) -> (usize, usize) {
+		(self.width, self.height)
+	}
+
+	/// Tiles in row-major order
+	pub fn get_data(&self) -> &[usize] {
+		&self.data
+	}
+
+	pub fn is_solved(&self) -> bool {
+		self.data == self.solution
+	}
+
+	/// Current state as a grid for the solvers
+	pub fn as_grid(&self) -> Result<Grid2D> {
+		to_grid(&self.data, self.width)
+	}
+
+	/// Expected solution as a grid for the solvers
+	pub fn goal_grid(&self) -> Result<Grid2D> {
+		to_grid(&self.solution, self.width)
+	}
+
 	fn get_index(&self, col: usize, row: usize) -> usize {
 		col + row * self.width
 	}
@@ -49,12 +103,6 @@ impl Board {
 		}
 	}
 
-	fn set_zero_xy(&mut self, x: i32, y: i32) {
-		if self.is_xy_in_bounds(x, y) {
-			self.zero_position = self.get_index(x as usize, y as usize);
-		}
-	}
-
 	fn set_zero_index(&mut self, i: usize) {
 		if i < self.width * self.height {
 			self.zero_position = i;
@@ -111,7 +159,7 @@ impl Board {
 			res.push(self.zero_position + self.width)
 		}
 		// Left
-		if self.zero_position % self.width > 0 {
+		if !self.zero_position.is_multiple_of(self.width) {
 			res.push(self.zero_position - 1)
 		}
 		// Right
@@ -121,32 +169,28 @@ impl Board {
 		res
 	}
 
-	pub fn shuffle(&mut self, steps: usize) {
-		if steps == 0 {
-			return;
-		}
+	/// Shuffles the board, the same `rng` state always yields the same board
+	///
+	/// The walk avoids the states it already visited, when all the
+	/// neighbours were visited it steps back through one of them
+	pub fn shuffle<R: Rng>(&mut self, steps: usize, rng: &mut R) {
 		let mut visited_states: HashSet<Vec<usize>> = HashSet::new();
-		for i in 0..steps {
+		for _ in 0..steps {
 			let mut possible_moves = self.possible_directions();
-			possible_moves.shuffle(&mut thread_rng());
-			let mut moved = false;
-			'consider: for current_move in possible_moves {
-				self.data.swap(self.zero_position, current_move);
-				if !visited_states.contains(&self.data) {
-					visited_states.insert(self.data.clone());
-					self.zero_position = current_move;
-					moved = true;
-					break 'consider;
-				} else {
-					self.data.swap(self.zero_position, current_move);
-				}
-			}
-			if moved == false {
-				panic!(
-					"all possible from this point already visited {}/{}",
-					i, steps
-				);
-			}
+			possible_moves.shuffle(rng);
+			let mut unvisited = possible_moves.iter().copied().filter(|&next| {
+				self.data.swap(self.zero_position, next);
+				let seen = visited_states.contains(&self.data);
+				self.data.swap(self.zero_position, next);
+				!seen
+			});
+			let next = match unvisited.next() {
+				Some(next) => next,
+				None => possible_moves[0],
+			};
+			self.data.swap(self.zero_position, next);
+			self.zero_position = next;
+			visited_states.insert(self.data.clone());
 		}
 	}
 
@@ -167,7 +211,7 @@ impl Board {
 				}
 			}
 			"left" | "l" => {
-				if self.zero_position % self.width > 0 {
+				if !self.zero_position.is_multiple_of(self.width) {
 					Some(self.zero_position - 1)
 				} else {
 					None
@@ -185,14 +229,17 @@ impl Board {
 	}
 
 	/// would be better outside board strucy
-	pub fn play(&mut self) {
+	///
+	/// Returns number of moves used if the puzzle was solved, `None` on exit
+	pub fn play(&mut self) -> Option<usize> {
+		let mut moves: usize = 0;
 		print!("\x1B[2J\x1B[1;1H");
 		println!("{}", self);
 		while self.data != self.solution {
 			let input: String = read!("{}\n");
 			match input.as_str() {
 				"exit" => {
-					return;
+					return None;
 				}
 				"help" => {
 					println!("Possible options: up / u, down / d, left / l, right / r, exit, help")
@@ -205,6 +252,7 @@ impl Board {
 					if let Some(move_to) = self.get_move(input.as_str()) {
 						self.data.swap(move_to, self.zero_position);
 						self.zero_position = move_to;
+						moves += 1;
 					} else {
 						continue;
 					}
@@ -213,6 +261,7 @@ impl Board {
 			print!("\x1B[2J\x1B[1;1H");
 			println!("{}", self);
 		}
+		Some(moves)
 	}
 
 	pub fn show_solution(&self) {
@@ -226,67 +275,138 @@ impl Board {
 		for i in 0..self.width * self.height {
 			res.push_str(&format!("{:width$} ", self.solution[i], width = longest));
 			if i % self.width == self.width - 1 {
-				res.push_str("\n");
+				res.push('\n');
 			}
 		}
 		println!("Expected Solution:\n{}", res);
 	}
 
-	pub fn from_str(input: &str) -> Result<Board> {
-		let mut board = Board {
-			width: 0,
-			height: 0,
-			data: Vec::new(),
-			solution: Vec::new(),
-			zero_position: 0,
-		};
-		let lines = input.split("\n");
-		for raw_line in lines {
-			let line = clean_line(raw_line);
-			let words: Vec<&str> = line.split_whitespace().collect();
-			if words.len() == 0 {
-				continue;
-			} else if words.len() == 1 && board.width == 0 {
-				if words[0].chars().all(char::is_numeric) {
-					board.width = words[0].parse::<usize>().unwrap();
-					board.height = board.width;
-				} else {
-					return Err(PuzzleError::Puzzle(ErrorKind::InvalidChar));
-				}
-			} else if board.width != 0 && words.len() == board.width {
-				for word in words {
-					if word.chars().all(char::is_numeric) {
-						let val = word.parse::<usize>().unwrap();
-						if val < board.width * board.width && !board.data.contains(&val) {
-							board.data.push(val);
-						} else {
-							return Err(PuzzleError::Puzzle(ErrorKind::InvalidValue));
-						}
-					} else {
-						return Err(PuzzleError::Puzzle(ErrorKind::InvalidChar));
+	/// Reads a single puzzle, `-` reads stdin
+	pub fn from_file(filename: &str, format: Format) -> Result<Board> {
+		let mut boards = Board::all_from_file(filename, format)?;
+		if let Some(i) = boards.iter().position(|board| board.is_err()) {
+			return boards.remove(i);
+		}
+		if boards.len() > 1 {
+			let mut err = ParseError::new(
+				ErrorKind::InvalidInput,
+				format!("expected a single puzzle, found {}", boards.len()),
+			);
+			err.file = Some(source_name(filename));
+			return Err(err.into());
+		}
+		boards.remove(0)
+	}
+
+	/// Reads every puzzle of a file, `-` reads stdin
+	///
+	/// Fails only if the file cannot be read, each puzzle is parsed on its own
+	/// so an invalid one does not prevent reading the rest.
+	pub fn all_from_file(filename: &str, format: Format) -> Result<Vec<Result<Board>>> {
+		let mut contents = String::new();
+		if filename == "-" {
+			io::stdin().read_to_string(&mut contents)?;
+		} else {
+			File::open(filename)
+				.and_then(|mut file| file.read_to_string(&mut contents))
+				.map_err(|e| io::Error::new(e.kind(), format!("{}: {}", filename, e)))?;
+		}
+		let boards = format
+			.parse_puzzles(&contents)
+			.into_iter()
+			.map(|board| {
+				board.map_err(|e| match e {
+					PuzzleError::Parse(mut err) => {
+						err.file = Some(source_name(filename));
+						PuzzleError::Parse(err)
 					}
+					e => e,
+				})
+			})
+			.collect();
+		Ok(boards)
+	}
+
+	/// Creates a square board from rows of tiles, validated like the text input
+	pub fn from_rows(rows: &[Vec<usize>]) -> Result<Board> {
+		let size = rows.len();
+		let error = |kind, reason| PuzzleError::from(ParseError::new(kind, reason));
+		if size < 2 {
+			return Err(error(
+				ErrorKind::InvalidNoSize,
+				String::from("puzzle size must be at least 2"),
+			));
+		}
+		let mut seen = vec![false; size * size];
+		for (r, row) in rows.iter().enumerate() {
+			if row.len() != size {
+				return Err(error(
+					ErrorKind::InvalidRowLength,
+					format!(
+						"row {} has {} values but width is {}",
+						r + 1,
+						row.len(),
+						size
+					),
+				));
+			}
+			for &val in row.iter() {
+				if val >= size * size {
+					return Err(error(
+						ErrorKind::InvalidValue,
+						format!("tile {} is too high, maximum is {}", val, size * size - 1),
+					));
+				}
+				if seen[val] {
+					return Err(error(
+						ErrorKind::DuplicateValue,
+						format!("duplicate tile {}", val),
+					));
 				}
+				seen[val] = true;
+			}
+		}
+		let mut board = Board::new(size, size);
+		board.data = rows.concat();
+		board.zero_position = board.data.iter().position(|&x| x == 0).unwrap();
+		Ok(board)
+	}
+
+	/// Rows of tiles, the inverse of `from_rows`
+	pub fn as_rows(&self) -> Vec<Vec<usize>> {
+		self.data
+			.chunks(self.width)
+			.map(|row| row.to_vec())
+			.collect()
+	}
+
+	/// Parses puzzles separated by blank lines or `---`, always at least one
+	///
+	/// Puzzles in compact notation may also simply follow each other, one
+	/// per line. Line numbers in errors are counted from the start of the
+	/// whole input.
+	pub fn parse_many(input: &str) -> Vec<Result<Board>> {
+		let mut boards = Vec::new();
+		let mut chunk: Vec<(usize, &str)> = Vec::new();
+		for (i, line) in input.lines().enumerate() {
+			let trimmed = line.trim();
+			if trimmed.is_empty() || trimmed == "---" {
+				parse_chunk(&chunk, &mut boards);
+				chunk.clear();
 			} else {
-				return Err(PuzzleError::Puzzle(ErrorKind::InvalidNoSize));
+				chunk.push((i + 1, line));
 			}
 		}
-		if let Some(zero_pos) = board.data.iter().position(|&x| x == 0) {
-			board.zero_position = zero_pos;
-		} else {
-			return Err(PuzzleError::Puzzle(ErrorKind::InvalidNoZero));
+		parse_chunk(&chunk, &mut boards);
+		if boards.is_empty() {
+			boards.push(parse_lines(&chunk));
 		}
-		let solution_board = Board::new(board.width, board.height);
-		board.solution = solution_board.solution.clone();
-		info!("Board read sucessfully from the file");
-		return Ok(board);
+		boards
 	}
 
-	pub fn from_file(filename: &str) -> Result<Board> {
-		let mut file = File::open(filename)?;
-		let mut contents = String::new();
-		file.read_to_string(&mut contents)?;
-		// while width and height == 0 search for size in the string
-		Board::from_str(&contents)
+	/// Single line notation, e.g. "3:1,2,3/8,0,4/7,6,5"
+	pub fn as_compact_string(&self) -> String {
+		compact_notation(self.width, &self.data)
 	}
 
 	pub fn as_output_string(&self) -> String {
@@ -297,6 +417,454 @@ impl Board {
 	}
 }
 
+impl FromStr for Board {
+	type Err = PuzzleError;
+
+	fn from_str(input: &str) -> Result<Board> {
+		let lines: Vec<(usize, &str)> =
+			input.lines().enumerate().map(|(i, l)| (i + 1, l)).collect();
+		parse_lines(&lines)
+	}
+}
+
+/// Parses a single puzzle from numbered lines
+fn parse_lines(lines: &[(usize, &str)]) -> Result<Board> {
+	let mut board = Board {
+		width: 0,
+		height: 0,
+		data: Vec::new(),
+		solution: Vec::new(),
+		zero_position: 0,
+		puzzletype: PuzzleType::Snail,
+	};
+	// line number where each value was found, to report duplicates
+	let mut seen: Vec<usize> = Vec::new();
+	let mut rows = 0;
+	let mut last_line = (0, "");
+	for &(line_no, raw_line) in lines.iter() {
+		let raw_line = raw_line.trim_end_matches('\r');
+		let words = tokens(clean_line(raw_line));
+		let error = |kind, reason: String, (column, token): (usize, &str)| {
+			PuzzleError::from(ParseError::new(kind, reason).at(line_no, column, token, raw_line))
+		};
+		if words.is_empty() {
+			continue;
+		}
+		last_line = (line_no, raw_line);
+		if board.width == 0 {
+			if is_compact(raw_line) {
+				return parse_single_compact(lines);
+			}
+			if words.len() != 1 {
+				return Err(error(
+					ErrorKind::InvalidNoSize,
+					format!("expected puzzle size, found {} values", words.len()),
+					words[0],
+				));
+			}
+			board.width = match words[0].1.parse::<usize>() {
+				Ok(size) if size >= 2 && tile_count(size).is_some() => size,
+				Ok(size) if size >= 2 => {
+					return Err(error(ErrorKind::InvalidNoSize, too_large(size), words[0]))
+				}
+				Ok(_) => {
+					return Err(error(
+						ErrorKind::InvalidNoSize,
+						String::from("puzzle size must be at least 2"),
+						words[0],
+					))
+				}
+				Err(_) => {
+					return Err(error(
+						ErrorKind::InvalidChar,
+						format!("invalid puzzle size '{}'", words[0].1),
+						words[0],
+					))
+				}
+			};
+			board.height = board.width;
+			seen = vec![0; tile_count(board.width).unwrap()];
+			continue;
+		}
+		rows += 1;
+		if rows > board.height {
+			return Err(error(
+				ErrorKind::TooManyRows,
+				format!("too many rows, expected {}", board.height),
+				words[0],
+			));
+		}
+		if words.len() != board.width {
+			let whole = (words[0].0, clean_line(raw_line).trim());
+			return Err(error(
+				ErrorKind::InvalidRowLength,
+				format!(
+					"row {} has {} values but width is {}",
+					rows,
+					words.len(),
+					board.width
+				),
+				whole,
+			));
+		}
+		for word in words {
+			if !word.1.chars().all(|c| c.is_ascii_digit()) {
+				return Err(error(ErrorKind::InvalidChar, invalid_tile(word.1), word));
+			}
+			let max = board.width * board.height - 1;
+			let val = match word.1.parse::<usize>() {
+				Ok(val) if val <= max => val,
+				_ => {
+					return Err(error(
+						ErrorKind::InvalidValue,
+						format!("tile {} is too high, maximum is {}", word.1, max),
+						word,
+					))
+				}
+			};
+			if seen[val] != 0 {
+				return Err(error(
+					ErrorKind::DuplicateValue,
+					format!("duplicate tile {}, first seen on line {}", val, seen[val]),
+					word,
+				));
+			}
+			seen[val] = line_no;
+			board.data.push(val);
+		}
+	}
+	if board.width == 0 {
+		return Err(ParseError::new(
+			ErrorKind::InvalidNoSize,
+			String::from("no puzzle size given"),
+		)
+		.into());
+	}
+	if rows < board.height {
+		let missing: Vec<String> = (0..seen.len())
+			.filter(|&v| seen[v] == 0)
+			.map(|v| v.to_string())
+			.collect();
+		let (line_no, raw_line) = last_line;
+		let trimmed = clean_line(raw_line).trim();
+		let column = raw_line.find(trimmed).unwrap_or(0) + 1;
+		return Err(ParseError::new(
+			ErrorKind::MissingValue,
+			format!(
+				"missing tile {} - expected {} rows, found {}",
+				missing.join(", "),
+				board.height,
+				rows
+			),
+		)
+		.at(line_no, column, trimmed, raw_line)
+		.into());
+	}
+	if let Some(zero_pos) = board.data.iter().position(|&x| x == 0) {
+		board.zero_position = zero_pos;
+	} else {
+		return Err(PuzzleError::Puzzle(ErrorKind::InvalidNoZero));
+	}
+	let solution_board = Board::new(board.width, board.height);
+	board.solution = solution_board.solution.clone();
+	info!("Board read sucessfully from the file");
+	Ok(board)
+}
+
+fn source_name(filename: &str) -> String {
+	match filename {
+		"-" => String::from("<stdin>"),
+		name => String::from(name),
+	}
+}
+
+fn parse_chunk(chunk: &[(usize, &str)], boards: &mut Vec<Result<Board>>) {
+	let content: Vec<(usize, &str)> = chunk
+		.iter()
+		.filter(|(_, line)| !clean_line(line).trim().is_empty())
+		.copied()
+		.collect();
+	match content.first() {
+		Some((_, first)) if is_compact(first) => {
+			for (line_no, line) in content {
+				if is_compact(line) {
+					boards.push(parse_compact(line_no, line));
+					continue;
+				}
+				// a lone number, a size line makes no sense between compact puzzles
+				let trimmed = clean_line(line).trim();
+				boards.push(Err(ParseError::new(
+					ErrorKind::InvalidInput,
+					String::from("expected a puzzle in compact notation"),
+				)
+				.at(line_no, column_of(line, trimmed), trimmed, line)
+				.into()));
+			}
+		}
+		Some(_) => boards.push(parse_lines(chunk)),
+		None => {}
+	}
+}
+
+/// Compact notation has the whole puzzle on one line, the 42 format starts
+/// with a line holding just the size
+fn is_compact(line: &str) -> bool {
+	let content = clean_line(line);
+	let count = tokens(content).len();
+	content.contains([':', ',', '/']) || (count >= 4 && (2..=count).any(|n| n * n == count))
+}
+
+/// Number of tiles of a square puzzle, none beyond `MAX_SIZE`
+fn tile_count(size: usize) -> Option<usize> {
+	size.checked_mul(size).filter(|_| size <= MAX_SIZE)
+}
+
+fn too_large(size: usize) -> String {
+	format!("puzzle size {} is too large, maximum is {}", size, MAX_SIZE)
+}
+
+/// Reason a tile is not a number, naming the first offending character
+fn invalid_tile(tile: &str) -> String {
+	match tile.chars().find(|c| !c.is_ascii_digit()) {
+		Some(c) => format!("invalid character '{}' in tile '{}'", c, tile),
+		None => format!("invalid tile '{}'", tile),
+	}
+}
+
+/// Single puzzle in compact notation, anything after it is an error
+fn parse_single_compact(lines: &[(usize, &str)]) -> Result<Board> {
+	let mut content = lines
+		.iter()
+		.filter(|(_, line)| !clean_line(line).trim().is_empty());
+	let &(line_no, line) = content.next().unwrap();
+	let board = parse_compact(line_no, line)?;
+	if let Some(&(line_no, line)) = content.next() {
+		let trimmed = clean_line(line).trim();
+		return Err(ParseError::new(
+			ErrorKind::TooManyRows,
+			String::from("unexpected line after a single-line puzzle"),
+		)
+		.at(line_no, column_of(line, trimmed), trimmed, line)
+		.into());
+	}
+	Ok(board)
+}
+
+/// Parses "N:r,o,w/r,o,w/..." where "N:" is optional, or a square number of
+/// tiles separated by commas or whitespace, such as the Korf's 100 instances
+fn parse_compact(line_no: usize, raw_line: &str) -> Result<Board> {
+	let raw_line = raw_line.trim_end_matches('\r');
+	let error = |kind, reason: String, (column, token): (usize, &str)| {
+		PuzzleError::from(ParseError::new(kind, reason).at(line_no, column, token, raw_line))
+	};
+	let content = clean_line(raw_line);
+	let (size, body) = match content.find(':') {
+		Some(i) => {
+			let size = compact_tokens(content, 0, i);
+			let size = match size.first() {
+				Some(row) if size.len() == 1 && row.len() == 1 => row[0],
+				_ => {
+					let trimmed = content[..i].trim();
+					return Err(error(
+						ErrorKind::InvalidNoSize,
+						String::from("expected puzzle size before ':'"),
+						(column_of(raw_line, trimmed), trimmed),
+					));
+				}
+			};
+			match size.1.parse::<usize>() {
+				Ok(n) if n >= 2 && tile_count(n).is_some() => {
+					(Some(n), compact_tokens(content, i + 1, content.len()))
+				}
+				Ok(n) if n >= 2 => return Err(error(ErrorKind::InvalidNoSize, too_large(n), size)),
+				_ => {
+					return Err(error(
+						ErrorKind::InvalidNoSize,
+						format!("invalid puzzle size '{}'", size.1),
+						size,
+					))
+				}
+			}
+		}
+		None => (None, compact_tokens(content, 0, content.len())),
+	};
+	let mut rows = body;
+	if rows.len() == 1 {
+		// flat list of tiles
+		let count = rows[0].len();
+		let n = size.unwrap_or_else(|| (1..=count).find(|n| n * n >= count).unwrap_or(0));
+		if n < 2 || n * n != count {
+			let first = rows[0].first().copied().unwrap_or((1, ""));
+			return Err(error(
+				ErrorKind::InvalidNoSize,
+				match size {
+					Some(n) => format!("expected {} tiles, found {}", n * n, count),
+					None => format!(
+						"expected puzzle size or a square number of tiles, found {} values",
+						count
+					),
+				},
+				first,
+			));
+		}
+		rows = rows[0].chunks(n).map(|row| row.to_vec()).collect();
+	}
+	let n = size.unwrap_or(rows.len());
+	if tile_count(n).is_none() {
+		let first = rows[0].first().copied().unwrap_or((1, ""));
+		return Err(error(ErrorKind::InvalidNoSize, too_large(n), first));
+	}
+	if rows.len() != n {
+		let last = *rows.last().and_then(|row| row.first()).unwrap_or(&(1, ""));
+		return Err(error(
+			if rows.len() > n {
+				ErrorKind::TooManyRows
+			} else {
+				ErrorKind::MissingValue
+			},
+			format!("expected {} rows, found {}", n, rows.len()),
+			last,
+		));
+	}
+	// line number style bookkeeping as in the 42 format, by row instead
+	let mut seen: Vec<Option<usize>> = vec![None; tile_count(n).unwrap()];
+	let mut tiles = Vec::with_capacity(n);
+	for (r, row) in rows.iter().enumerate() {
+		if row.len() != n {
+			let first = row.first().copied().unwrap_or((1, ""));
+			return Err(error(
+				ErrorKind::InvalidRowLength,
+				format!("row {} has {} values but width is {}", r + 1, row.len(), n),
+				first,
+			));
+		}
+		let mut values = Vec::with_capacity(n);
+		for &word in row.iter() {
+			let val = match word.1.parse::<usize>() {
+				Ok(val) if val < n * n => val,
+				Ok(_) => {
+					return Err(error(
+						ErrorKind::InvalidValue,
+						format!("tile {} is too high, maximum is {}", word.1, n * n - 1),
+						word,
+					))
+				}
+				Err(_) => return Err(error(ErrorKind::InvalidChar, invalid_tile(word.1), word)),
+			};
+			if let Some(first) = seen[val] {
+				return Err(error(
+					ErrorKind::DuplicateValue,
+					format!("duplicate tile {}, first seen in row {}", val, first),
+					word,
+				));
+			}
+			seen[val] = Some(r + 1);
+			values.push(val);
+		}
+		tiles.push(values);
+	}
+	Board::from_rows(&tiles)
+}
+
+/// Rows split by '/' of tokens split by ',' or whitespace, with 1-based
+/// columns, within the given byte range of the line
+fn compact_tokens(line: &str, from: usize, to: usize) -> Vec<Vec<(usize, &str)>> {
+	let mut rows = vec![Vec::new()];
+	let mut start: Option<usize> = None;
+	for (i, c) in line[from..to]
+		.char_indices()
+		.map(|(i, c)| (i + from, c))
+		.chain(std::iter::once((to, ' ')))
+	{
+		let separator = c.is_whitespace() || c == ',' || c == '/';
+		match (separator, start) {
+			(true, Some(s)) => {
+				rows.last_mut()
+					.unwrap()
+					.push((line[..s].chars().count() + 1, &line[s..i]));
+				start = None;
+			}
+			(false, None) => start = Some(i),
+			_ => {}
+		}
+		if c == '/' && i < to {
+			rows.push(Vec::new());
+		}
+	}
+	rows
+}
+
+fn column_of(line: &str, part: &str) -> usize {
+	line.find(part).map_or(1, |i| line[..i].chars().count() + 1)
+}
+
+/// JSON form of a board, the goal defaults to snail when reading
+#[derive(Serialize, Deserialize)]
+struct BoardJson {
+	size: usize,
+	#[serde(default)]
+	goal: Option<PuzzleType>,
+	tiles: Vec<Vec<usize>>,
+}
+
+impl Serialize for Board {
+	fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		BoardJson {
+			size: self.width,
+			goal: Some(self.puzzletype),
+			tiles: self.as_rows(),
+		}
+		.serialize(serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for Board {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+		let json = BoardJson::deserialize(deserializer)?;
+		if json.tiles.len() != json.size {
+			return Err(de::Error::custom(format!(
+				"expected {} rows, found {}",
+				json.size,
+				json.tiles.len()
+			)));
+		}
+		let mut board = Board::from_rows(&json.tiles).map_err(|e| match e {
+			PuzzleError::Parse(err) => de::Error::custom(err.reason),
+			e => de::Error::custom(e),
+		})?;
+		if let Some(goal) = json.goal {
+			board.set_puzzletype(goal);
+		}
+		Ok(board)
+	}
+}
+
+/// Whitespace separated words with their 1-based columns
+fn tokens(line: &str) -> Vec<(usize, &str)> {
+	let mut words = Vec::new();
+	let mut start: Option<usize> = None;
+	for (i, c) in line
+		.char_indices()
+		.chain(std::iter::once((line.len(), ' ')))
+	{
+		match (c.is_whitespace(), start) {
+			(true, Some(s)) => {
+				words.push((line[..s].chars().count() + 1, &line[s..i]));
+				start = None;
+			}
+			(false, None) => start = Some(i),
+			_ => {}
+		}
+	}
+	words
+}
+
+fn to_grid(data: &[usize], width: usize) -> Result<Grid2D> {
+	let mut grid = Grid2D::new();
+	grid.from_2dvec(data.chunks(width).map(|row| row.to_vec()).collect())?;
+	Ok(grid)
+}
+
 fn clean_line(input_string: &str) -> &str {
 	if let Some(comment) = input_string.find("#") {
 		&input_string[0..comment]
@@ -317,9 +885,145 @@ impl fmt::Display for Board {
 		for i in 0..self.width * self.height {
 			res.push_str(&format!("{:width$} ", self.data[i], width = longest));
 			if i % self.width == self.width - 1 {
-				res.push_str("\n");
+				res.push('\n');
 			}
 		}
 		write!(f, "{}", res)
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn state_file(name: &str) -> String {
+		format!(
+			"{}/tests/npuzzle_states/{}",
+			env!("CARGO_MANIFEST_DIR"),
+			name
+		)
+	}
+
+	fn parse_file(name: &str) -> Result<Board> {
+		Board::from_file(&state_file(name), Format::Text)
+	}
+
+	fn parse_error(input: &str) -> ParseError {
+		match Board::from_str(input) {
+			Err(PuzzleError::Parse(err)) => err,
+			other => panic!("expected parse error, got {:?}", other),
+		}
+	}
+
+	fn file_error(name: &str) -> ParseError {
+		match parse_file(name) {
+			Err(PuzzleError::Parse(err)) => err,
+			other => panic!("expected parse error, got {:?}", other),
+		}
+	}
+
+	#[test]
+	fn subject_files() {
+		for name in ["basic/00_subject", "basic/01_subject", "basic/02_subject"].iter() {
+			assert!(parse_file(name).is_ok());
+		}
+		let err = file_error("basic_errors/invalid_character");
+		assert_eq!(ErrorKind::InvalidChar, err.kind);
+		assert_eq!((4, 3, "b"), (err.line, err.column, &err.token[..]));
+		assert_eq!("invalid character 'b' in tile 'b'", err.reason);
+		assert!(err.file.unwrap().ends_with("invalid_character"));
+		let err = file_error("basic_errors/invalid_value_00");
+		assert_eq!(ErrorKind::InvalidValue, err.kind);
+		assert_eq!("tile 14 is too high, maximum is 8", err.reason);
+		let err = file_error("basic_errors/invalid_value_01");
+		assert_eq!(ErrorKind::DuplicateValue, err.kind);
+		assert_eq!("duplicate tile 7, first seen on line 4", err.reason);
+		assert_eq!((5, 3), (err.line, err.column));
+		let err = file_error("basic_errors/no_size");
+		assert_eq!(ErrorKind::InvalidNoSize, err.kind);
+	}
+
+	#[test]
+	fn diagnostics() {
+		let err = parse_error("3\n1 2 3\n8 0\n7 6 5\n");
+		assert_eq!(ErrorKind::InvalidRowLength, err.kind);
+		assert_eq!("row 2 has 2 values but width is 3", err.reason);
+		let err = parse_error("3\n1 2 3\n8 0 4\n7 6 5\n1 1 1\n");
+		assert_eq!(ErrorKind::TooManyRows, err.kind);
+		assert_eq!(5, err.line);
+		let err = parse_error("3\n1 2 3\n8 0 4 # no last row\n");
+		assert_eq!(ErrorKind::MissingValue, err.kind);
+		assert_eq!(
+			"missing tile 5, 6, 7 - expected 3 rows, found 2",
+			err.reason
+		);
+		let err = parse_error("3\n1 2 3\n8  5 4\n7 6 5\n");
+		assert_eq!(
+			"error: duplicate tile 5, first seen on line 3\n --> <input>:4:5\n  |\n4 | 7 6 5\n  |     ^",
+			err.to_string()
+		);
+		assert_eq!(ErrorKind::InvalidNoSize, parse_error("# empty\n").kind);
+		// the size header is checked before anything is allocated from it
+		let err = parse_error("100000\n1 2\n");
+		assert_eq!(ErrorKind::InvalidNoSize, err.kind);
+		assert_eq!(
+			"puzzle size 100000 is too large, maximum is 1000",
+			err.reason
+		);
+		assert_eq!((1, 1, "100000"), (err.line, err.column, &err.token[..]));
+		let err = parse_error("9999999999\n1 2\n");
+		assert_eq!(ErrorKind::InvalidNoSize, err.kind);
+		assert_eq!(
+			"puzzle size 9999999999 is too large, maximum is 1000",
+			err.reason
+		);
+	}
+
+	#[test]
+	fn multiple_puzzles() {
+		let input = "# corpus\n2\n1 2\n0 3\n---\n# second\n2\n1 2\n3 3\n\n\n2\n1 2\n3 0\n\n";
+		let boards = Board::parse_many(input);
+		assert_eq!(3, boards.len());
+		assert_eq!(&[1, 2, 0, 3], boards[0].as_ref().unwrap().get_data());
+		match &boards[1] {
+			Err(PuzzleError::Parse(err)) => assert_eq!((9, 3), (err.line, err.column)),
+			other => panic!("expected parse error, got {:?}", other),
+		}
+		assert_eq!(&[1, 2, 3, 0], boards[2].as_ref().unwrap().get_data());
+		assert_eq!(1, Board::parse_many("# nothing\n").len());
+	}
+
+	#[test]
+	fn compact_notation() {
+		let board = Board::from_str("3:1,2,3/8,0,4/7,6,5").unwrap();
+		assert_eq!("3:1,2,3/8,0,4/7,6,5", board.as_compact_string());
+		let korf = "14 13 15 7 11 12 9 5 6 0 2 1 4 8 10 3";
+		let board = Board::from_str(korf).unwrap();
+		assert_eq!((4, 4), board.get_size());
+		assert_eq!(0, board.get_data()[9]);
+		let err = parse_error("3:1,2,3/8,0,4/7,6,4");
+		assert_eq!(ErrorKind::DuplicateValue, err.kind);
+		assert_eq!((1, 19), (err.line, err.column));
+		let err = parse_error("3:1,2,3/8,0,4");
+		assert_eq!(ErrorKind::MissingValue, err.kind);
+		let err = parse_error("2:1,2/3,0x");
+		assert_eq!("invalid character 'x' in tile '0x'", err.reason);
+		// not a square number of values, a size line with extra values
+		let err = parse_error("3 3\n1 2 3\n8 0 4\n7 6 5\n");
+		assert_eq!(ErrorKind::InvalidNoSize, err.kind);
+		assert_eq!("expected puzzle size, found 2 values", err.reason);
+		// sizes are bounded before the tiles are counted or allocated
+		let err = parse_error("9999999999:1,2");
+		assert_eq!(ErrorKind::InvalidNoSize, err.kind);
+		assert_eq!(
+			"puzzle size 9999999999 is too large, maximum is 1000",
+			err.reason
+		);
+		assert_eq!((1, "9999999999"), (err.column, &err.token[..]));
+		let err = parse_error(&vec!["1"; MAX_SIZE + 1].join("/"));
+		assert_eq!("puzzle size 1001 is too large, maximum is 1000", err.reason);
+		let boards = Board::parse_many("# corpus\n1 2 3 0\n2:1,2/0,3\n\n2\n1 2\n3 0\n");
+		assert_eq!(3, boards.len());
+		assert!(boards.iter().all(|board| board.is_ok()));
+	}
+}
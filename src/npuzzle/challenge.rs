@@ -0,0 +1,127 @@
+//! Seeded challenge puzzles for the play mode
+//!
+//! Everybody playing the same challenge code with the same size and goal
+//! gets the same puzzle, by default the code is today's date.
+
+use chrono::Local;
+
+use super::{PuzzleError, PuzzleType, Result};
+
+/// Default number of shuffle iterations of a challenge puzzle
+pub const CHALLENGE_ITERATIONS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Challenge {
+	code: String,
+}
+
+impl Challenge {
+	/// Daily challenge
+	pub fn today() -> Self {
+		Self {
+			code: Local::now().format("%Y-%m-%d").to_string(),
+		}
+	}
+
+	/// Challenge of given code, which is a single word so that it can be
+	/// kept in the scores file
+	pub fn from_code(code: &str) -> Result<Self> {
+		let code = code.trim();
+		if code.is_empty() || code.contains(char::is_whitespace) {
+			return Err(PuzzleError::Custom(format!(
+				"Invalid challenge code '{}', expected a single word",
+				code
+			)));
+		}
+		Ok(Self {
+			code: String::from(code),
+		})
+	}
+
+	pub fn get_code(&self) -> &str {
+		&self.code
+	}
+
+	/// Shuffle seed of the challenge
+	///
+	/// Uses FNV-1a rather than std hasher, which is not guaranteed
+	/// to stay the same across Rust releases
+	pub fn seed(&self, size: usize, iterations: usize, puzzletype: PuzzleType) -> u64 {
+		let key = format!("{}:{}:{}:{}", self.code, size, iterations, puzzletype);
+		key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+			(hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+		})
+	}
+
+	/// Result line to be shared with others playing the same challenge
+	pub fn share_line(
+		&self,
+		size: usize,
+		puzzletype: PuzzleType,
+		moves: usize,
+		optimal: Option<usize>,
+	) -> String {
+		let verdict = match optimal {
+			Some(optimal) if moves <= optimal => format!("optimal {} - perfect!", optimal),
+			Some(optimal) => format!("optimal {}, +{}", optimal, moves - optimal),
+			None => String::from("optimal unknown"),
+		};
+		format!(
+			"npuzzle challenge {} {}x{} {}: {} moves ({})",
+			self.code, size, size, puzzletype, moves, verdict
+		)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::Board;
+	use super::*;
+	use rand::{rngs::StdRng, SeedableRng};
+	use std::collections::HashSet;
+
+	#[test]
+	fn same_code_same_seed() {
+		let a = Challenge::from_code("team-42").unwrap();
+		let b = Challenge::from_code(" team-42 ").unwrap();
+		assert_eq!(a, b);
+		assert!(Challenge::from_code("team 42").is_err());
+		assert!(Challenge::from_code(" ").is_err());
+		assert_eq!(
+			a.seed(3, CHALLENGE_ITERATIONS, PuzzleType::Snail),
+			b.seed(3, CHALLENGE_ITERATIONS, PuzzleType::Snail)
+		);
+		assert_ne!(
+			a.seed(3, CHALLENGE_ITERATIONS, PuzzleType::Snail),
+			a.seed(4, CHALLENGE_ITERATIONS, PuzzleType::Snail)
+		);
+		assert_eq!(
+			"npuzzle challenge team-42 3x3 snail: 22 moves (optimal 20, +2)",
+			a.share_line(3, PuzzleType::Snail, 22, Some(20))
+		);
+	}
+
+	#[test]
+	fn every_code_gives_a_puzzle() {
+		// shuffled as the launcher does, the walk used to get stuck
+		let board = |code: &str| {
+			let seed = Challenge::from_code(code).unwrap().seed(
+				3,
+				CHALLENGE_ITERATIONS,
+				PuzzleType::Snail,
+			);
+			let mut board = Board::with_type(3, 3, PuzzleType::Snail);
+			board.shuffle(CHALLENGE_ITERATIONS, &mut StdRng::seed_from_u64(seed));
+			board.get_data().to_vec()
+		};
+		let mut boards = HashSet::new();
+		for i in 0..500 {
+			let code = format!("2021-{:03}", i);
+			let data = board(&code);
+			assert_eq!(data, board(&format!(" {}\n", code)));
+			boards.insert(data);
+		}
+		// a few of the 181440 states are bound to come up twice
+		assert!(boards.len() > 490);
+	}
+}
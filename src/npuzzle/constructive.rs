@@ -0,0 +1,539 @@
+//! Constructive solver for boards too big for the exact searches
+//!
+//! Solves the board the way people do: the outer rows and columns of the
+//! unsolved rectangle are finished one at a time, never the one holding the
+//! goal position of the blank, until the rectangle is small enough for a
+//! breadth-first search. Tiles of a line are brought to their place one by
+//! one by the shortest sequence of moves leaving the finished part alone,
+//! the last two together by a search in the 3x2 corner of the rectangle.
+//! Every step is polynomial in the size of the board, the solution is far
+//! from the shortest one.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::time::Instant;
+
+use super::grid2d::Direction;
+use super::grid_traits::Grid;
+use super::solver::{is_solvable, Limits, Observer, Partial, Progress, Solution, Solver};
+use super::{ErrorKind, PuzzleError, Result};
+
+/// Rectangles of at most this many cells are solved by a search
+const SMALL: usize = 6;
+
+/// Unsolved part of the board, inclusive bounds
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+	top: usize,
+	bottom: usize,
+	left: usize,
+	right: usize,
+}
+
+impl Rect {
+	fn height(&self) -> usize {
+		self.bottom - self.top + 1
+	}
+
+	fn width(&self) -> usize {
+		self.right - self.left + 1
+	}
+}
+
+/// Line of the rectangle being finished, in coordinates where the line is
+/// row 0, its cells are columns 0..len and the rows grow into the rectangle
+struct Frame {
+	origin: (isize, isize),
+	/// Global step of a local row and of a local column
+	down: (isize, isize),
+	along: (isize, isize),
+	len: usize,
+}
+
+impl Frame {
+	/// Global (row, column) of a local cell
+	fn at(&self, row: usize, col: usize) -> (usize, usize) {
+		let (row, col) = (row as isize, col as isize);
+		(
+			(self.origin.0 + row * self.down.0 + col * self.along.0) as usize,
+			(self.origin.1 + row * self.down.1 + col * self.along.1) as usize,
+		)
+	}
+}
+
+/// Board being solved with the moves done so far
+struct Work<'l> {
+	width: usize,
+	height: usize,
+	tiles: Vec<usize>,
+	goal: Vec<usize>,
+	blank: usize,
+	/// Cells of the finished part
+	fixed: Vec<bool>,
+	moves: Vec<Direction>,
+	expanded: usize,
+	peak: usize,
+	limits: &'l Limits,
+	observer: Option<&'l mut Box<dyn Observer>>,
+	timer: Instant,
+}
+
+impl<'l> Work<'l> {
+	fn cell(&self, (row, col): (usize, usize)) -> usize {
+		row * self.width + col
+	}
+
+	fn position(&self, tile: usize) -> usize {
+		self.tiles.iter().position(|&t| t == tile).unwrap()
+	}
+
+	/// Neighbouring cells with the direction of a blank moving there
+	fn neighbours(&self, cell: usize) -> impl Iterator<Item = (usize, Direction)> {
+		let (row, col, width, height) = (
+			cell / self.width,
+			cell % self.width,
+			self.width,
+			self.height,
+		);
+		let mut next = Vec::with_capacity(4);
+		if row > 0 {
+			next.push((cell - width, Direction::Up));
+		}
+		if row + 1 < height {
+			next.push((cell + width, Direction::Down));
+		}
+		if col + 1 < width {
+			next.push((cell + 1, Direction::Right));
+		}
+		if col > 0 {
+			next.push((cell - 1, Direction::Left));
+		}
+		next.into_iter()
+	}
+
+	/// Moves the blank to a neighbouring cell
+	fn slide(&mut self, to: usize) {
+		let (_, dir) = self.neighbours(self.blank).find(|&(c, _)| c == to).unwrap();
+		self.tiles.swap(self.blank, to);
+		self.blank = to;
+		self.moves.push(dir);
+	}
+
+	/// Path of the blank through the cells allowed, the blank excluded
+	fn replay(&mut self, cells: Vec<usize>) {
+		for cell in cells {
+			self.slide(cell);
+		}
+	}
+
+	/// Brings a tile to a target cell without touching the fixed cells
+	///
+	/// The tile follows its shortest path with the blank going around it to
+	/// the next cell each time, the exact search is left for the rare cases
+	/// the blank cannot get there.
+	fn move_tile(&mut self, tile: usize, target: usize) -> Result<()> {
+		let distance = self.distances(target);
+		loop {
+			let at = self.position(tile);
+			if at == target {
+				return Ok(());
+			}
+			let next = self
+				.neighbours(at)
+				.map(|(cell, _)| cell)
+				.filter(|&cell| distance[cell] < distance[at])
+				.min_by_key(|&cell| distance[cell])
+				.ok_or(PuzzleError::Puzzle(ErrorKind::Unsolvable))?;
+			match self.blank_path(next, at) {
+				Ok(path) => {
+					self.replay(path);
+					self.slide(at);
+				}
+				Err(_) => return self.move_tile_exactly(tile, target),
+			}
+		}
+	}
+
+	/// Number of moves from every free cell to given one
+	fn distances(&mut self, target: usize) -> Vec<usize> {
+		let mut distance = vec![usize::MAX; self.tiles.len()];
+		let mut queue = VecDeque::new();
+		distance[target] = 0;
+		queue.push_back(target);
+		while let Some(cell) = queue.pop_front() {
+			self.expanded += 1;
+			for (next, _) in self.neighbours(cell) {
+				if !self.fixed[next] && distance[next] == usize::MAX {
+					distance[next] = distance[cell] + 1;
+					queue.push_back(next);
+				}
+			}
+		}
+		distance
+	}
+
+	/// Shortest sequence of moves bringing a tile to a target cell without
+	/// touching the fixed cells, a search over positions of the tile and
+	/// of the blank
+	fn move_tile_exactly(&mut self, tile: usize, target: usize) -> Result<()> {
+		let start = (self.position(tile), self.blank);
+		let mut parents: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+		let mut queue = VecDeque::new();
+		parents.insert(start, start);
+		queue.push_back(start);
+		let mut found = None;
+		while let Some((tile_at, blank)) = queue.pop_front() {
+			if tile_at == target {
+				found = Some((tile_at, blank));
+				break;
+			}
+			self.expanded += 1;
+			for (next, _) in self.neighbours(blank) {
+				if self.fixed[next] {
+					continue;
+				}
+				let state = match next == tile_at {
+					true => (blank, next),
+					false => (tile_at, next),
+				};
+				if let Entry::Vacant(entry) = parents.entry(state) {
+					entry.insert((tile_at, blank));
+					queue.push_back(state);
+				}
+			}
+		}
+		self.peak = self.peak.max(parents.len());
+		let mut state = found.ok_or(PuzzleError::Puzzle(ErrorKind::Unsolvable))?;
+		let mut cells = Vec::new();
+		while state != start {
+			cells.push(state.1);
+			state = parents[&state];
+		}
+		cells.reverse();
+		self.replay(cells);
+		Ok(())
+	}
+
+	/// Shortest sequence of moves inside given cells, the blank among them,
+	/// reaching an arrangement accepted by `done`
+	fn search_window(&mut self, window: &[usize], done: impl Fn(&[usize]) -> bool) -> Result<()> {
+		let start: Vec<usize> = window.iter().map(|&c| self.tiles[c]).collect();
+		let mut parents: HashMap<Vec<usize>, (Vec<usize>, usize)> = HashMap::new();
+		let mut queue = VecDeque::new();
+		parents.insert(start.clone(), (Vec::new(), 0));
+		queue.push_back(start.clone());
+		let mut found = None;
+		while let Some(arrangement) = queue.pop_front() {
+			if done(&arrangement) {
+				found = Some(arrangement);
+				break;
+			}
+			self.expanded += 1;
+			let blank = arrangement.iter().position(|&t| t == 0).unwrap();
+			for (next, _) in self.neighbours(window[blank]) {
+				if let Some(i) = window.iter().position(|&c| c == next) {
+					let mut moved = arrangement.clone();
+					moved.swap(blank, i);
+					if !parents.contains_key(&moved) {
+						parents.insert(moved.clone(), (arrangement.clone(), window[i]));
+						queue.push_back(moved);
+					}
+				}
+			}
+		}
+		self.peak = self.peak.max(parents.len());
+		let mut arrangement = found.ok_or(PuzzleError::Puzzle(ErrorKind::Unsolvable))?;
+		let mut cells = Vec::new();
+		while arrangement != start {
+			let (previous, cell) = parents.remove(&arrangement).unwrap();
+			cells.push(cell);
+			arrangement = previous;
+		}
+		cells.reverse();
+		self.replay(cells);
+		Ok(())
+	}
+
+	/// Finishes row 0 of the frame, leaving it fixed
+	fn solve_line(&mut self, frame: &Frame) -> Result<()> {
+		let len = frame.len;
+		for col in 0..len - 2 {
+			let target = self.cell(frame.at(0, col));
+			self.move_tile(self.goal[target], target)?;
+			self.fixed[target] = true;
+			self.check()?;
+		}
+		// the last two cannot be placed one after the other
+		let (first, last) = (
+			self.cell(frame.at(0, len - 2)),
+			self.cell(frame.at(0, len - 1)),
+		);
+		let (x, y) = (self.goal[first], self.goal[last]);
+		if self.tiles[first] == x && self.tiles[last] == y {
+			self.fixed[first] = true;
+			self.fixed[last] = true;
+			return self.check();
+		}
+		let window: Vec<usize> = (0..3)
+			.flat_map(|row| (len - 2..len).map(move |col| (row, col)))
+			.map(|(row, col)| self.cell(frame.at(row, col)))
+			.collect();
+		// both into the window, the blank after them without moving them
+		self.move_tile(y, first)?;
+		self.fixed[first] = true;
+		if !window.contains(&self.position(x)) {
+			self.move_tile(x, window[4])?;
+		}
+		self.fixed[first] = false;
+		if !window.contains(&self.blank) {
+			let (tile_x, tile_y) = (self.position(x), self.position(y));
+			self.fixed[tile_x] = true;
+			let path = window
+				.iter()
+				.filter(|&&cell| cell != tile_x && cell != tile_y)
+				.find_map(|&cell| self.blank_path(cell, tile_y).ok());
+			self.fixed[tile_x] = false;
+			self.replay(path.ok_or(PuzzleError::Puzzle(ErrorKind::Unsolvable))?);
+		}
+		self.search_window(&window, |a| a[0] == x && a[1] == y)?;
+		self.fixed[first] = true;
+		self.fixed[last] = true;
+		self.check()
+	}
+
+	/// Shortest path of the blank to a cell, around the fixed cells and
+	/// given tile position
+	fn blank_path(&mut self, target: usize, avoid: usize) -> Result<Vec<usize>> {
+		let mut parents = vec![usize::MAX; self.tiles.len()];
+		let mut queue = VecDeque::new();
+		parents[self.blank] = self.blank;
+		queue.push_back(self.blank);
+		while let Some(cell) = queue.pop_front() {
+			if cell == target {
+				let mut cells = Vec::new();
+				let mut cell = cell;
+				while cell != self.blank {
+					cells.push(cell);
+					cell = parents[cell];
+				}
+				cells.reverse();
+				return Ok(cells);
+			}
+			self.expanded += 1;
+			for (next, _) in self.neighbours(cell) {
+				if !self.fixed[next] && next != avoid && parents[next] == usize::MAX {
+					parents[next] = cell;
+					queue.push_back(next);
+				}
+			}
+		}
+		Err(PuzzleError::Puzzle(ErrorKind::Unsolvable))
+	}
+
+	/// Checks the limits and reports the progress after each line
+	fn check(&mut self) -> Result<()> {
+		let left = self.fixed.iter().filter(|&&f| !f).count();
+		let memory = self.peak * 2 * std::mem::size_of::<usize>();
+		let mut stop = self
+			.limits
+			.max_nodes
+			.filter(|&max| self.expanded > max)
+			.map(|_| ErrorKind::NodeLimit)
+			.or_else(|| self.limits.resources_exceeded(memory, &self.timer));
+		if let Some(observer) = self.observer.as_mut() {
+			let progress = Progress {
+				nodes: self.expanded,
+				open: left,
+				closed: self.fixed.len() - left,
+				bound: self.moves.len(),
+				best_h: left,
+				elapsed: self.timer.elapsed(),
+			};
+			if stop.is_none() && !observer.progress(&progress) {
+				stop = Some(ErrorKind::Interrupted);
+			}
+		}
+		match stop {
+			Some(limit) => Err(PuzzleError::Limit(Box::new(Partial {
+				limit,
+				moves: self.moves.clone(),
+				estimate: left,
+				time_complexity: self.expanded,
+				size_complexity: self.peak,
+				duration: self.timer.elapsed(),
+			}))),
+			None => Ok(()),
+		}
+	}
+
+	fn solve(&mut self) -> Result<()> {
+		let goal_blank = self.goal.iter().position(|&t| t == 0).unwrap();
+		let (blank_row, blank_col) = (goal_blank / self.width, goal_blank % self.width);
+		let mut rect = Rect {
+			top: 0,
+			bottom: self.height - 1,
+			left: 0,
+			right: self.width - 1,
+		};
+		while rect.height() * rect.width() > SMALL && rect.height().min(rect.width()) > 1 {
+			let frame = if rect.height() >= rect.width() {
+				let row = match blank_row != rect.top {
+					true => rect.top,
+					false => rect.bottom,
+				};
+				let down = if row == rect.top { 1 } else { -1 };
+				let frame = Frame {
+					origin: (row as isize, rect.left as isize),
+					down: (down, 0),
+					along: (0, 1),
+					len: rect.width(),
+				};
+				match row == rect.top {
+					true => rect.top += 1,
+					false => rect.bottom -= 1,
+				}
+				frame
+			} else {
+				let col = match blank_col != rect.left {
+					true => rect.left,
+					false => rect.right,
+				};
+				let down = if col == rect.left { 1 } else { -1 };
+				let frame = Frame {
+					origin: (rect.top as isize, col as isize),
+					down: (0, down),
+					along: (1, 0),
+					len: rect.height(),
+				};
+				match col == rect.left {
+					true => rect.left += 1,
+					false => rect.right -= 1,
+				}
+				frame
+			};
+			self.solve_line(&frame)?;
+		}
+		let window: Vec<usize> = (rect.top..=rect.bottom)
+			.flat_map(|row| (rect.left..=rect.right).map(move |col| (row, col)))
+			.map(|cell| self.cell(cell))
+			.collect();
+		let goal: Vec<usize> = window.iter().map(|&c| self.goal[c]).collect();
+		self.search_window(&window, |a| a == goal.as_slice())
+	}
+}
+
+pub struct Constructive<G: Grid> {
+	start: G,
+	destination: G,
+	time_complex: usize,
+	size_complex: usize,
+	limits: Limits,
+	observer: Option<Box<dyn Observer>>,
+}
+
+impl<G> Constructive<G>
+where
+	G: Grid<M = Direction> + Clone + Hash + Eq,
+{
+	pub fn new(start: G, destination: G) -> Self {
+		Self {
+			start,
+			destination,
+			time_complex: 0,
+			size_complex: 0,
+			limits: Limits::default(),
+			observer: None,
+		}
+	}
+}
+
+impl<G> Solver for Constructive<G>
+where
+	G: Grid<M = Direction> + Clone + Hash + Eq,
+{
+	/// Finds a path, not the shortest one, line by line
+	fn solve(&mut self) -> Result<Solution> {
+		let timer = Instant::now();
+		if !is_solvable(&self.start, &self.destination) {
+			return Err(PuzzleError::Puzzle(ErrorKind::Unsolvable));
+		}
+		let (width, height) = self.start.dim();
+		let tiles: Vec<usize> = (0..width * height)
+			.map(|i| self.start.get_indexed(i))
+			.collect();
+		let mut work = Work {
+			width,
+			height,
+			blank: tiles.iter().position(|&t| t == 0).unwrap(),
+			tiles,
+			goal: (0..width * height)
+				.map(|i| self.destination.get_indexed(i))
+				.collect(),
+			fixed: vec![false; width * height],
+			moves: Vec::new(),
+			expanded: 0,
+			peak: 0,
+			limits: &self.limits,
+			observer: self.observer.as_mut(),
+			timer,
+		};
+		let result = work.solve();
+		self.time_complex = work.expanded;
+		self.size_complex = work.peak;
+		result?;
+		Ok(Solution {
+			moves: work.moves,
+			time_complexity: self.time_complex,
+			size_complexity: self.size_complex,
+			duration: timer.elapsed(),
+			thread_nodes: Vec::new(),
+		})
+	}
+
+	fn set_limits(&mut self, limits: Limits) {
+		self.limits = limits;
+	}
+
+	fn set_observer(&mut self, observer: Box<dyn Observer>) {
+		self.observer = Some(observer);
+	}
+
+	fn get_time_complexity(&self) -> usize {
+		self.time_complex
+	}
+
+	fn get_size_complexity(&self) -> usize {
+		self.size_complex
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::bench::generate;
+	use super::super::PuzzleType;
+	use super::*;
+
+	#[test]
+	fn large_boards() {
+		for &(size, iterations, puzzletype) in [
+			(3, 100, PuzzleType::Snail),
+			(4, 300, PuzzleType::Lines00),
+			(5, 500, PuzzleType::LinesNN),
+			(10, 3000, PuzzleType::Snail),
+			(12, 4000, PuzzleType::Lines00),
+		]
+		.iter()
+		{
+			for job in generate(size, 2, iterations, 11, puzzletype) {
+				let board = job.board.unwrap();
+				let (start, goal) = (board.as_grid().unwrap(), board.goal_grid().unwrap());
+				let solution = Constructive::new(start.clone(), goal.clone())
+					.solve()
+					.unwrap();
+				let states = solution.states(&start).unwrap();
+				assert_eq!(goal, *states.last().unwrap(), "{} {}", size, puzzletype);
+			}
+		}
+	}
+}
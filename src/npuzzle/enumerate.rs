@@ -0,0 +1,255 @@
+//! Complete enumeration of the small puzzles
+//!
+//! A breadth-first search from the goal reaches every solvable state of the
+//! board, half of the permutations of its tiles, and gives the exact number
+//! of moves from each of them to the goal. The distances are stored by the
+//! rank of the permutation, a byte each, so the 8-puzzle takes 363 kB and
+//! the 2x5 board 3.6 MB; larger boards are out of reach.
+//!
+//! Besides the distribution of the distances the table answers the length
+//! of the shortest solution of any state at once, and walks down to the
+//! goal along one, which makes it an oracle to check the other solvers.
+
+use serde::ser::{SerializeStruct, Serializer};
+use serde::Serialize;
+use std::fmt;
+
+use super::grid2d::Direction;
+use super::grid_traits::Grid;
+use super::rank::{rank, unrank};
+use super::utils::format_table;
+use super::{PuzzleError, Result};
+
+/// Largest number of tiles, blank included, of an enumerated board
+pub const MAX_CELLS: usize = 10;
+
+/// Distance of the permutations the goal cannot be reached from
+const UNREACHABLE: u8 = u8::MAX;
+
+/// Distance to the goal of every state of a board
+pub struct DistanceTable {
+	width: usize,
+	height: usize,
+	goal: Vec<usize>,
+	/// Indexed by the rank of the permutation of the tiles
+	distances: Vec<u8>,
+	/// Number of states at each distance
+	histogram: Vec<usize>,
+}
+
+impl DistanceTable {
+	/// Enumerates all states of the goal's board by a breadth-first search
+	pub fn new<G: Grid>(goal: &G) -> Result<Self> {
+		let (width, height) = goal.dim();
+		let cells = width * height;
+		if cells > MAX_CELLS {
+			return Err(PuzzleError::Custom(format!(
+				"{}x{} has too many states to enumerate, at most {} tiles",
+				width, height, MAX_CELLS
+			)));
+		}
+		let goal: Vec<usize> = (0..cells).map(|i| goal.get_indexed(i)).collect();
+		let mut distances = vec![UNREACHABLE; (1..=cells).product()];
+		let mut histogram = vec![1];
+		distances[rank(&goal) as usize] = 0;
+		// ranks of the states in the order of their distance
+		let mut queue = vec![rank(&goal) as u32];
+		let mut next = 0;
+		while next < queue.len() {
+			let current = queue[next] as usize;
+			next += 1;
+			let distance = distances[current];
+			let mut tiles = unrank(current as u64, cells);
+			let blank = tiles.iter().position(|&t| t == 0).unwrap();
+			for cell in neighbours(width, height, blank) {
+				tiles.swap(blank, cell);
+				let index = rank(&tiles) as usize;
+				if distances[index] == UNREACHABLE {
+					distances[index] = distance + 1;
+					match histogram.get_mut(distance as usize + 1) {
+						Some(count) => *count += 1,
+						None => histogram.push(1),
+					}
+					queue.push(index as u32);
+				}
+				tiles.swap(blank, cell);
+			}
+		}
+		Ok(Self {
+			width,
+			height,
+			goal,
+			distances,
+			histogram,
+		})
+	}
+
+	/// Length of the shortest solution, none for unsolvable states or
+	/// states of another board
+	pub fn distance<G: Grid>(&self, state: &G) -> Option<usize> {
+		if state.dim() != (self.width, self.height) {
+			return None;
+		}
+		let tiles: Vec<usize> = (0..self.goal.len()).map(|i| state.get_indexed(i)).collect();
+		if tiles.iter().any(|&t| t >= tiles.len()) {
+			return None;
+		}
+		match self.distances[rank(&tiles) as usize] {
+			UNREACHABLE => None,
+			distance => Some(distance as usize),
+		}
+	}
+
+	/// One of the shortest solutions, each move going one step closer
+	pub fn path<G>(&self, state: &G) -> Option<Vec<Direction>>
+	where
+		G: Grid<M = Direction> + Clone,
+	{
+		let mut state = state.clone();
+		let mut distance = self.distance(&state)?;
+		let mut moves = Vec::with_capacity(distance);
+		while distance > 0 {
+			let dir = state.possible_moves().into_iter().find(|dir| {
+				let mut next = state.clone();
+				next.do_move(dir).is_ok() && self.distance(&next) == Some(distance - 1)
+			})?;
+			state.do_move(&dir).ok()?;
+			moves.push(dir);
+			distance -= 1;
+		}
+		Some(moves)
+	}
+
+	/// Number of solvable states
+	pub fn reachable(&self) -> usize {
+		self.histogram.iter().sum()
+	}
+
+	/// Length of the longest shortest solution, the diameter of the board
+	pub fn max_depth(&self) -> usize {
+		self.histogram.len() - 1
+	}
+
+	/// Number of states at each distance from the goal
+	pub fn histogram(&self) -> &[usize] {
+		&self.histogram
+	}
+
+	/// Tiles of the first state, by rank, of the longest shortest solution
+	pub fn farthest(&self) -> Vec<usize> {
+		let deepest = self.max_depth() as u8;
+		let index = self.distances.iter().position(|&d| d == deepest).unwrap();
+		unrank(index as u64, self.goal.len())
+	}
+
+	pub fn dim(&self) -> (usize, usize) {
+		(self.width, self.height)
+	}
+
+	/// Pretty printed JSON of the summary
+	pub fn to_json(&self) -> Result<String> {
+		serde_json::to_string_pretty(self).map_err(|e| PuzzleError::Custom(e.to_string()))
+	}
+}
+
+/// Summary only, the distances themselves are too many
+impl Serialize for DistanceTable {
+	fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		let rows = |tiles: &[usize]| -> Vec<Vec<usize>> {
+			tiles.chunks(self.width).map(|row| row.to_vec()).collect()
+		};
+		let mut s = serializer.serialize_struct("DistanceTable", 7)?;
+		s.serialize_field("width", &self.width)?;
+		s.serialize_field("height", &self.height)?;
+		s.serialize_field("goal", &rows(&self.goal))?;
+		s.serialize_field("states", &self.reachable())?;
+		s.serialize_field("max_depth", &self.max_depth())?;
+		s.serialize_field("histogram", &self.histogram)?;
+		s.serialize_field("farthest", &rows(&self.farthest()))?;
+		s.end()
+	}
+}
+
+impl fmt::Display for DistanceTable {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let total = self.reachable() as f64;
+		let rows: Vec<Vec<String>> = self
+			.histogram
+			.iter()
+			.enumerate()
+			.map(|(depth, &count)| {
+				vec![
+					depth.to_string(),
+					count.to_string(),
+					format!("{:.3}", count as f64 * 100.0 / total),
+				]
+			})
+			.collect();
+		writeln!(f, "{}", format_table(&["moves", "states", "%"], &rows, 0))?;
+		writeln!(f, "Reachable states: {}", self.reachable())?;
+		writeln!(f, "Maximum depth: {}", self.max_depth())?;
+		write!(f, "Farthest state:")?;
+		for row in self.farthest().chunks(self.width) {
+			let row: Vec<String> = row.iter().map(|t| format!("{:>2}", t)).collect();
+			write!(f, "\n{}", row.join(" "))?;
+		}
+		Ok(())
+	}
+}
+
+/// Cells next to given one
+fn neighbours(width: usize, height: usize, cell: usize) -> impl Iterator<Item = usize> {
+	let (row, col) = (cell / width, cell % width);
+	IntoIterator::into_iter([
+		(row > 0).then(|| cell - width),
+		(row + 1 < height).then(|| cell + width),
+		(col + 1 < width).then(|| cell + 1),
+		(col > 0).then(|| cell - 1),
+	])
+	.flatten()
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::astar::Astar;
+	use super::super::bench::generate;
+	use super::super::grid2d::Grid2D;
+	use super::super::heuristic::Heuristic;
+	use super::super::solver::Solver;
+	use super::super::PuzzleType;
+	use super::*;
+
+	fn template(width: usize, height: usize, puzzletype: PuzzleType) -> Grid2D {
+		let mut goal = Grid2D::new();
+		goal.from_2dvec(puzzletype.get_template(width, height))
+			.unwrap();
+		goal
+	}
+
+	#[test]
+	fn small_boards() {
+		let goal = template(3, 2, PuzzleType::Snail);
+		let table = DistanceTable::new(&goal).unwrap();
+		assert_eq!((360, 21), (table.reachable(), table.max_depth()));
+		assert_eq!(Some(0), table.distance(&goal));
+		assert!(DistanceTable::new(&template(4, 3, PuzzleType::Snail)).is_err());
+		let table = DistanceTable::new(&template(3, 3, PuzzleType::Lines00)).unwrap();
+		assert_eq!((181_440, 31), (table.reachable(), table.max_depth()));
+		// the other solvers find the shortest paths the table knows
+		for job in generate(3, 20, 200, 4, PuzzleType::Lines00) {
+			let board = job.board.unwrap();
+			let (start, goal) = (board.as_grid().unwrap(), board.goal_grid().unwrap());
+			let optimal = Astar::new(start.clone(), goal.clone(), Heuristic::LinearConflict)
+				.solve()
+				.unwrap();
+			assert_eq!(Some(optimal.len()), table.distance(&start));
+			let path = table.path(&start).unwrap();
+			assert_eq!(optimal.len(), path.len());
+			let mut state = start.clone();
+			for dir in path.iter() {
+				state.do_move(dir).unwrap();
+			}
+			assert_eq!(goal, state);
+		}
+	}
+}
@@ -0,0 +1,266 @@
+//! Input and output formats - the 42 text format, compact notation and JSON
+//!
+//! Compact notation puts a puzzle on a single line, e.g. `3:1,2,3/8,0,4/7,6,5`,
+//! the size prefix is optional and so are the row separators when the tiles
+//! make a square, e.g. the Korf's `14 13 15 7 11 12 9 5 6 0 2 1 4 8 10 3`.
+//! It is recognised in text input, `--output-format compact` prints it.
+//!
+//! JSON schema of a puzzle, read with `--input-format json` and written by
+//! `generate --output-format json`:
+//!
+//! ```text
+//! {"size": 3, "goal": "snail", "tiles": [[1, 2, 3], [8, 0, 4], [7, 6, 5]]}
+//! ```
+//!
+//! `goal` is one of "snail", "lines00" and "linesnn", snail when missing. It is
+//! the goal of the puzzle read unless `--goal` is given, which overrides it.
+//! The input may hold several puzzles, either in an array or as one object
+//! after another.
+//!
+//! `solve --output-format json` prints one object per line for each puzzle:
+//!
+//! ```text
+//! {"status": "solved", "puzzle": {..}, "verified": true,
+//!  "solution": {"moves": 2, "path": "ul", "time_complexity": 2,
+//!               "size_complexity": 6, "search_time_ms": 0.04},
+//!  "states": [[[1, 2, 3], [8, 4, 0], [7, 6, 5]], ..]}
+//! {"status": "error", "message": "Given state is unsolvable", "exit_code": 5}
+//! {"status": "limit", "message": "Search limit exceeded: timeout", "exit_code": 7,
+//!  "partial": {"limit": "timeout", "moves": 31, "path": "ulld..", "estimate": 12,
+//!              "time_complexity": 81920, "size_complexity": 160514,
+//!              "search_time_ms": 1000.3}}
+//! ```
+//!
+//! `states` go from the input to the goal, `exit_code` is the code the
+//! program exits with for the error. A search stopped by a limit gives the
+//! path to the state closest to the goal by the heuristic `estimate`.
+//! `parallel-ida-star` adds the states expanded by each of its threads to
+//! the solution as `"thread_nodes": [..]`. With `--optimize` the report has
+//! the moves saved by each pass, `"optimized": {"reversals": 12, "loops": 0,
+//! "windows": 4}`.
+
+use serde::Serialize;
+use serde_json::{Deserializer, Value};
+use std::fmt;
+use std::str::FromStr;
+
+use super::optimize::Savings;
+use super::solver::{Partial, Solution};
+use super::{Board, ErrorKind, ParseError, PuzzleError, Result};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+	Text,
+	Compact,
+	Json,
+}
+
+impl Format {
+	pub fn as_str_array() -> &'static [&'static str] {
+		&["text", "compact", "json"]
+	}
+
+	pub fn as_str(&self) -> &'static str {
+		match *self {
+			Self::Text => "text",
+			Self::Compact => "compact",
+			Self::Json => "json",
+		}
+	}
+
+	/// Parses all puzzles of the input in this format
+	pub fn parse_puzzles(&self, input: &str) -> Vec<Result<Board>> {
+		match *self {
+			Self::Text | Self::Compact => Board::parse_many(input),
+			Self::Json => parse_json_puzzles(input),
+		}
+	}
+}
+
+impl FromStr for Format {
+	type Err = PuzzleError;
+
+	fn from_str(format: &str) -> Result<Self> {
+		match format {
+			"text" => Ok(Self::Text),
+			"compact" => Ok(Self::Compact),
+			"json" => Ok(Self::Json),
+			_ => Err(PuzzleError::Puzzle(ErrorKind::InvalidInput)),
+		}
+	}
+}
+
+impl fmt::Display for Format {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.as_str())
+	}
+}
+
+/// Row-major tiles in compact notation, e.g. "3:1,2,3/8,0,4/7,6,5"
+pub fn compact_notation(width: usize, tiles: &[usize]) -> String {
+	let rows: Vec<String> = tiles
+		.chunks(width)
+		.map(|row| {
+			row.iter()
+				.map(|v| v.to_string())
+				.collect::<Vec<String>>()
+				.join(",")
+		})
+		.collect();
+	format!("{}:{}", width, rows.join("/"))
+}
+
+/// Puzzles given as objects, arrays of objects or a mix of both
+///
+/// Invalid JSON ends the input, an invalid puzzle is reported on its own.
+fn parse_json_puzzles(input: &str) -> Vec<Result<Board>> {
+	let mut boards = Vec::new();
+	for value in Deserializer::from_str(input).into_iter::<Value>() {
+		let value = match value {
+			Ok(value) => value,
+			Err(e) => {
+				boards.push(Err(json_error(input, &e)));
+				break;
+			}
+		};
+		let puzzles = match value {
+			Value::Array(puzzles) => puzzles,
+			puzzle => vec![puzzle],
+		};
+		for puzzle in puzzles {
+			let index = boards.len() + 1;
+			boards.push(serde_json::from_value(puzzle).map_err(|e| {
+				ParseError::new(ErrorKind::InvalidInput, format!("puzzle {}: {}", index, e)).into()
+			}));
+		}
+	}
+	if boards.is_empty() {
+		boards.push(Err(ParseError::new(
+			ErrorKind::InvalidNoSize,
+			String::from("no puzzle given"),
+		)
+		.into()));
+	}
+	boards
+}
+
+/// Syntax error pointing at its line and column
+fn json_error(input: &str, e: &serde_json::Error) -> PuzzleError {
+	let message = e.to_string();
+	let reason = message.split(" at line ").next().unwrap_or(&message);
+	let err = ParseError::new(ErrorKind::InvalidInput, format!("invalid JSON: {}", reason));
+	match input.lines().nth(e.line().saturating_sub(1)) {
+		Some(line) if e.line() > 0 => {
+			// serde_json reports the column after the offending character
+			let column = e.column().max(1);
+			let token: String = line.chars().skip(column - 1).take(1).collect();
+			err.at(e.line(), column, &token, line).into()
+		}
+		_ => err.into(),
+	}
+}
+
+/// JSON output of solving a single puzzle
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum SolveReport<'a, G: Serialize> {
+	Solved {
+		puzzle: &'a Board,
+		solution: &'a Solution,
+		states: &'a [G],
+		verified: bool,
+		#[serde(skip_serializing_if = "Option::is_none")]
+		optimized: Option<&'a Savings>,
+	},
+	Error {
+		message: String,
+		exit_code: i32,
+	},
+	Limit {
+		message: String,
+		exit_code: i32,
+		partial: &'a Partial,
+	},
+}
+
+impl<'a, G: Serialize> SolveReport<'a, G> {
+	pub fn error(err: &'a PuzzleError) -> Self {
+		match err {
+			PuzzleError::Limit(partial) => Self::Limit {
+				message: err.to_string(),
+				exit_code: err.exit_code(),
+				partial,
+			},
+			_ => Self::Error {
+				message: err.to_string(),
+				exit_code: err.exit_code(),
+			},
+		}
+	}
+
+	/// Single line of JSON
+	pub fn to_json(&self) -> Result<String> {
+		serde_json::to_string(self).map_err(|e| PuzzleError::Custom(e.to_string()))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::astar::Astar;
+	use super::super::bench::generate;
+	use super::super::grid_traits::Grid;
+	use super::super::heuristic::Heuristic;
+	use super::super::solver::Solver;
+	use super::super::PuzzleType;
+	use super::*;
+
+	#[test]
+	fn json_puzzles() {
+		let input = r#"{"size": 2, "goal": "snail", "tiles": [[1, 2], [0, 3]]}
+			[{"size": 2, "tiles": [[1, 2], [3, 3]]}, {"size": 2, "tiles": [[3, 0], [2, 1]]}]
+			{"size": 2, "tiles": [[1, 2]"#;
+		let boards = Format::Json.parse_puzzles(input);
+		assert_eq!(4, boards.len());
+		assert_eq!(&[1, 2, 0, 3], boards[0].as_ref().unwrap().get_data());
+		match &boards[1] {
+			Err(PuzzleError::Parse(err)) => {
+				assert_eq!("puzzle 2: duplicate tile 3", err.reason)
+			}
+			other => panic!("expected parse error, got {:?}", other),
+		}
+		assert_eq!(&[3, 0, 2, 1], boards[2].as_ref().unwrap().get_data());
+		match &boards[3] {
+			Err(PuzzleError::Parse(err)) => assert_eq!(3, err.line),
+			other => panic!("expected parse error, got {:?}", other),
+		}
+		let board = boards[0].as_ref().unwrap();
+		let json = serde_json::to_string(board).unwrap();
+		assert_eq!(r#"{"size":2,"goal":"snail","tiles":[[1,2],[0,3]]}"#, json);
+		let grid = serde_json::to_string(&board.as_grid().unwrap()).unwrap();
+		assert_eq!("[[1,2],[0,3]]", grid);
+	}
+
+	#[test]
+	fn json_goal_round_trip() {
+		let jobs = generate(3, 3, 5, 7, PuzzleType::LinesNN);
+		let boards: Vec<Board> = jobs.into_iter().map(|job| job.board.unwrap()).collect();
+		let input = serde_json::to_string(&boards).unwrap();
+		for board in Format::Json.parse_puzzles(&input) {
+			let board = board.unwrap();
+			assert_eq!(PuzzleType::LinesNN, board.get_puzzletype());
+			let (mut state, goal) = (board.as_grid().unwrap(), board.goal_grid().unwrap());
+			let solution = Astar::new(state.clone(), goal.clone(), Heuristic::Manhattan)
+				.solve()
+				.unwrap();
+			for dir in solution.moves.iter() {
+				state.do_move(dir).unwrap();
+			}
+			assert_eq!(goal, state);
+		}
+		let missing = Format::Json.parse_puzzles(r#"{"size": 2, "tiles": [[1, 2], [0, 3]]}"#);
+		assert_eq!(
+			PuzzleType::Snail,
+			missing[0].as_ref().unwrap().get_puzzletype()
+		);
+	}
+}
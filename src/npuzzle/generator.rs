@@ -3,11 +3,15 @@ use super::status::{ErrorKind, PuzzleError, Result};
 use super::utils::is_in_bounds;
 use log::*;
 use rand::{seq::SliceRandom, thread_rng};
+use serde::{Deserialize, Serialize};
 use std::cmp::Eq;
 use std::collections::HashSet;
+use std::fmt;
 use std::hash::Hash;
+use std::str::FromStr;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PuzzleType {
 	Snail,   // subject given snail spiral solution
 	Lines00, // empty at the beginning
@@ -15,6 +19,18 @@ pub enum PuzzleType {
 }
 
 impl PuzzleType {
+	pub fn as_str_array() -> &'static [&'static str] {
+		&["snail", "lines00", "linesnn"]
+	}
+
+	pub fn as_str(&self) -> &'static str {
+		match *self {
+			PuzzleType::Snail => "snail",
+			PuzzleType::Lines00 => "lines00",
+			PuzzleType::LinesNN => "linesnn",
+		}
+	}
+
 	pub fn get_template(&self, width: usize, height: usize) -> Vec<Vec<usize>> {
 		match self {
 			PuzzleType::Snail => snail_template(width, height),
@@ -24,6 +40,25 @@ impl PuzzleType {
 	}
 }
 
+impl FromStr for PuzzleType {
+	type Err = PuzzleError;
+
+	fn from_str(puzzletype: &str) -> Result<Self> {
+		match puzzletype {
+			"snail" => Ok(PuzzleType::Snail),
+			"lines00" => Ok(PuzzleType::Lines00),
+			"linesnn" => Ok(PuzzleType::LinesNN),
+			_ => Err(PuzzleError::Puzzle(ErrorKind::InvalidInput)),
+		}
+	}
+}
+
+impl fmt::Display for PuzzleType {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.as_str())
+	}
+}
+
 /// Sliding puzzle template generators - consider better organisation
 fn snail_template(width: usize, height: usize) -> Vec<Vec<usize>> {
 	let mut grid: Vec<Vec<usize>> = Vec::with_capacity(height);
@@ -140,7 +175,7 @@ where
 					self.grid.undo_move(&current_move)?;
 				}
 			}
-			if moved == false {
+			if !moved {
 				warn!(
 					"all possible from this point already visited {}/{}",
 					i, iterations
@@ -164,36 +199,39 @@ mod test {
 	#[test]
 	fn test_snail_template() {
 		let g = snail_template(5, 5);
-		let mut expected: Vec<Vec<usize>> = Vec::with_capacity(5);
-		expected.push(vec![1, 2, 3, 4, 5]);
-		expected.push(vec![16, 17, 18, 19, 6]);
-		expected.push(vec![15, 24, 0, 20, 7]);
-		expected.push(vec![14, 23, 22, 21, 8]);
-		expected.push(vec![13, 12, 11, 10, 9]);
+		let expected: Vec<Vec<usize>> = vec![
+			vec![1, 2, 3, 4, 5],
+			vec![16, 17, 18, 19, 6],
+			vec![15, 24, 0, 20, 7],
+			vec![14, 23, 22, 21, 8],
+			vec![13, 12, 11, 10, 9],
+		];
 		assert_eq!(expected, g);
 	}
 
 	#[test]
 	fn test_lines00_template() {
 		let g = lines00_template(5, 5);
-		let mut expected: Vec<Vec<usize>> = Vec::with_capacity(5);
-		expected.push(vec![0, 1, 2, 3, 4]);
-		expected.push(vec![5, 6, 7, 8, 9]);
-		expected.push(vec![10, 11, 12, 13, 14]);
-		expected.push(vec![15, 16, 17, 18, 19]);
-		expected.push(vec![20, 21, 22, 23, 24]);
+		let expected: Vec<Vec<usize>> = vec![
+			vec![0, 1, 2, 3, 4],
+			vec![5, 6, 7, 8, 9],
+			vec![10, 11, 12, 13, 14],
+			vec![15, 16, 17, 18, 19],
+			vec![20, 21, 22, 23, 24],
+		];
 		assert_eq!(expected, g);
 	}
 
 	#[test]
 	fn test_sn_template() {
 		let g = linesnn_template(5, 5);
-		let mut expected: Vec<Vec<usize>> = Vec::with_capacity(5);
-		expected.push(vec![1, 2, 3, 4, 5]);
-		expected.push(vec![6, 7, 8, 9, 10]);
-		expected.push(vec![11, 12, 13, 14, 15]);
-		expected.push(vec![16, 17, 18, 19, 20]);
-		expected.push(vec![21, 22, 23, 24, 0]);
+		let expected: Vec<Vec<usize>> = vec![
+			vec![1, 2, 3, 4, 5],
+			vec![6, 7, 8, 9, 10],
+			vec![11, 12, 13, 14, 15],
+			vec![16, 17, 18, 19, 20],
+			vec![21, 22, 23, 24, 0],
+		];
 		assert_eq!(expected, g);
 	}
 
@@ -1,39 +1,55 @@
 //! 2D grid representation of the problem
 use super::grid_traits::Grid;
 use log::*;
+use serde::{Deserialize, Serialize, Serializer};
 use std::cmp::PartialEq;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::ops::{Add, Sub};
 
 use super::{ErrorKind, PuzzleError, Result};
 
-#[derive(Debug, Clone, Copy)]
+/// Serialized as its single letter, e.g. "u"
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Direction {
+	#[serde(rename = "u")]
 	Up,
+	#[serde(rename = "d")]
 	Down,
+	#[serde(rename = "r")]
 	Right,
+	#[serde(rename = "l")]
 	Left,
 }
 
 impl Direction {
-	/// Safe
-	fn try_from(coords: Coords) -> Result<Self> {
-		match coords {
-			Coords(1, 0) => Ok(Self::Up),
-			Coords(-1, 0) => Ok(Self::Down),
-			Coords(0, 1) => Ok(Self::Right),
-			Coords(0, -1) => Ok(Self::Left),
-			_ => Err(PuzzleError::Puzzle(ErrorKind::InvalidPoint)),
-		}
-	}
-
 	fn iterator() -> impl Iterator<Item = Direction> {
 		[Self::Up, Self::Down, Self::Right, Self::Left]
 			.iter()
 			.copied()
 	}
 
-	fn opposite(self) -> Self {
+	/// Single letter notation of the move, as used in the solution path
+	pub fn as_char(&self) -> char {
+		match *self {
+			Self::Up => 'u',
+			Self::Down => 'd',
+			Self::Right => 'r',
+			Self::Left => 'l',
+		}
+	}
+
+	pub fn from_char(c: char) -> Option<Self> {
+		match c.to_ascii_lowercase() {
+			'u' => Some(Self::Up),
+			'd' => Some(Self::Down),
+			'r' => Some(Self::Right),
+			'l' => Some(Self::Left),
+			_ => None,
+		}
+	}
+
+	pub fn opposite(self) -> Self {
 		match self {
 			Self::Up => Self::Down,
 			Self::Down => Self::Up,
@@ -74,7 +90,7 @@ impl From<Direction> for Coords {
 	}
 }
 
-#[derive(Clone, Debug, Hash, Eq)]
+#[derive(Clone, Debug, Eq)]
 pub struct Grid2D {
 	height: usize,
 	width: usize,
@@ -94,8 +110,8 @@ impl Grid2D {
 
 	pub fn with_capacity(height: usize, width: usize) -> Self {
 		Self {
-			width: width,
-			height: height,
+			width,
+			height,
 			data: vec![Vec::with_capacity(width); height],
 			zero_position: Coords(0, 0),
 		}
@@ -175,6 +191,11 @@ impl Grid for Grid2D {
 		}
 	}
 
+	/// Retrieves value at given row-major index, panics if out of bounds
+	fn get_indexed(&self, index: usize) -> usize {
+		self.data[index / self.width][index % self.width]
+	}
+
 	/// Retrieves dimensions of the grid
 	fn dim(&self) -> (usize, usize) {
 		(self.width, self.height)
@@ -205,6 +226,13 @@ impl Grid for Grid2D {
 	}
 }
 
+/// Serialized as rows of tiles, e.g. [[1, 2], [3, 0]]
+impl Serialize for Grid2D {
+	fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		self.data.serialize(serializer)
+	}
+}
+
 impl fmt::Display for Grid2D {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		if self.data.is_empty() {
@@ -221,7 +249,7 @@ impl fmt::Display for Grid2D {
 				for col in 0..self.width {
 					out.push_str(&format!("{:width$}", self.data[row][col], width = longest));
 				}
-				out.push_str("\n");
+				out.push('\n');
 			}
 			write!(f, "{}", out)
 		}
@@ -234,6 +262,18 @@ impl PartialEq for Grid2D {
 	}
 }
 
+impl Hash for Grid2D {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.data.hash(state);
+	}
+}
+
+impl Default for Grid2D {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 #[cfg(test)]
 mod test {
 	use super::*;
@@ -22,10 +22,15 @@ pub trait Grid {
 	/// To get a value
 	fn get_value(&self, position: Self::P) -> Result<usize>;
 
+	/// To get a value by its row-major index, meant for fast iteration
+	/// over all the values (e.g. heuristics)
+	fn get_indexed(&self, index: usize) -> usize;
+
 	/// Returns (width, height)
 	fn dim(&self) -> (usize, usize);
 
 	/// retrieves data for its internal structure from 2d vector
 	/// Consider if single vector is sufficient in use cases
+	#[allow(clippy::wrong_self_convention)]
 	fn from_2dvec(&mut self, data: Vec<Vec<usize>>) -> Result<()>;
 }
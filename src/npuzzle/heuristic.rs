@@ -0,0 +1,193 @@
+//! Heuristic functions estimating the distance of a state from the goal
+//!
+//! All of them are admissible - they never overestimate the real distance,
+//! therefore A* and its variants using them return optimal solutions.
+//!     - Hamming - number of misplaced tiles
+//!     - Manhattan - sum of distances of all tiles from their goal positions
+//!     - Linear conflict - Manhattan plus 2 moves for every tile that has to
+//!       leave its goal row or column to let another tile pass
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use super::grid_traits::Grid;
+use super::{ErrorKind, PuzzleError, Result};
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Heuristic {
+	Hamming,
+	Manhattan,
+	LinearConflict,
+}
+
+impl Heuristic {
+	pub fn as_str_array() -> &'static [&'static str] {
+		&["hamming", "manhattan", "linear-conflict"]
+	}
+
+	pub fn as_str(&self) -> &'static str {
+		match *self {
+			Heuristic::Hamming => "hamming",
+			Heuristic::Manhattan => "manhattan",
+			Heuristic::LinearConflict => "linear-conflict",
+		}
+	}
+}
+
+impl FromStr for Heuristic {
+	type Err = PuzzleError;
+
+	fn from_str(heuristic: &str) -> Result<Self> {
+		match heuristic {
+			"hamming" => Ok(Heuristic::Hamming),
+			"manhattan" => Ok(Heuristic::Manhattan),
+			"linear-conflict" => Ok(Heuristic::LinearConflict),
+			_ => Err(PuzzleError::Puzzle(ErrorKind::InvalidInput)),
+		}
+	}
+}
+
+impl fmt::Display for Heuristic {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.as_str())
+	}
+}
+
+/// Heuristic bound to a specific goal state
+#[derive(Debug, Clone)]
+pub struct Evaluator {
+	heuristic: Heuristic,
+	width: usize,
+	height: usize,
+	/// (row, col) of every value in the goal state
+	goal: Vec<(usize, usize)>,
+}
+
+impl Evaluator {
+	pub fn new<G: Grid>(heuristic: Heuristic, goal: &G) -> Self {
+		let (width, height) = goal.dim();
+		let mut positions = vec![(0, 0); width * height];
+		for i in 0..width * height {
+			positions[goal.get_indexed(i)] = (i / width, i % width);
+		}
+		Self {
+			heuristic,
+			width,
+			height,
+			goal: positions,
+		}
+	}
+
+	pub fn get_heuristic(&self) -> Heuristic {
+		self.heuristic
+	}
+
+	/// Goal position (row, col) of given value
+	pub fn goal_position(&self, value: usize) -> (usize, usize) {
+		self.goal[value]
+	}
+
+	/// Estimated number of moves from given state to the goal
+	pub fn estimate<G: Grid>(&self, grid: &G) -> usize {
+		match self.heuristic {
+			Heuristic::Hamming => self.hamming(grid),
+			Heuristic::Manhattan => self.manhattan(grid),
+			Heuristic::LinearConflict => self.manhattan(grid) + self.linear_conflict(grid),
+		}
+	}
+
+	fn hamming<G: Grid>(&self, grid: &G) -> usize {
+		(0..self.width * self.height)
+			.filter(|&i| {
+				let value = grid.get_indexed(i);
+				value != 0 && self.goal[value] != (i / self.width, i % self.width)
+			})
+			.count()
+	}
+
+	fn manhattan<G: Grid>(&self, grid: &G) -> usize {
+		let mut total = 0;
+		for i in 0..self.width * self.height {
+			let value = grid.get_indexed(i);
+			if value != 0 {
+				let (row, col) = self.goal[value];
+				total += row.abs_diff(i / self.width) + col.abs_diff(i % self.width);
+			}
+		}
+		total
+	}
+
+	/// Extra moves on top of Manhattan distance
+	///
+	/// Tiles that are in their goal line but in wrong order have to get out
+	/// of the way. Tiles that can stay form the longest increasing sequence
+	/// of goal positions, every other one costs at least 2 extra moves.
+	fn linear_conflict<G: Grid>(&self, grid: &G) -> usize {
+		let mut total = 0;
+		let mut line: Vec<usize> = Vec::with_capacity(self.width.max(self.height));
+		for row in 0..self.height {
+			line.clear();
+			for col in 0..self.width {
+				let value = grid.get_indexed(row * self.width + col);
+				if value != 0 && self.goal[value].0 == row {
+					line.push(self.goal[value].1);
+				}
+			}
+			total += 2 * (line.len() - longest_increasing(&line));
+		}
+		for col in 0..self.width {
+			line.clear();
+			for row in 0..self.height {
+				let value = grid.get_indexed(row * self.width + col);
+				if value != 0 && self.goal[value].1 == col {
+					line.push(self.goal[value].0);
+				}
+			}
+			total += 2 * (line.len() - longest_increasing(&line));
+		}
+		total
+	}
+}
+
+/// Length of the longest strictly increasing subsequence, lines are short
+fn longest_increasing(values: &[usize]) -> usize {
+	let mut lengths = vec![1; values.len()];
+	for i in 0..values.len() {
+		for j in 0..i {
+			if values[j] < values[i] && lengths[j] + 1 > lengths[i] {
+				lengths[i] = lengths[j] + 1;
+			}
+		}
+	}
+	lengths.into_iter().max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::grid2d::Grid2D;
+	use super::super::PuzzleType;
+	use super::*;
+
+	fn grid(data: Vec<Vec<usize>>) -> Grid2D {
+		let mut grid = Grid2D::new();
+		grid.from_2dvec(data).unwrap();
+		grid
+	}
+
+	#[test]
+	fn estimates() {
+		let goal = grid(PuzzleType::LinesNN.get_template(3, 3));
+		let state = grid(vec![vec![3, 2, 1], vec![4, 5, 6], vec![7, 8, 0]]);
+		let hamming = Evaluator::new(Heuristic::Hamming, &goal);
+		let manhattan = Evaluator::new(Heuristic::Manhattan, &goal);
+		let conflict = Evaluator::new(Heuristic::LinearConflict, &goal);
+		assert_eq!(0, manhattan.estimate(&goal));
+		assert_eq!(0, conflict.estimate(&goal));
+		assert_eq!(2, hamming.estimate(&state));
+		assert_eq!(4, manhattan.estimate(&state));
+		// 3 2 1 needs two of the tiles to leave the row
+		assert_eq!(8, conflict.estimate(&state));
+	}
+}
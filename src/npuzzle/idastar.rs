@@ -0,0 +1,256 @@
+//! Iterative deepening A* for sliding puzzle
+//!
+//! Depth-first searches with an increasing bound on f = g + h, each one
+//! raising the bound to the smallest f that exceeded the previous one.
+//! Only the current path is held in memory, the price is re-expanding
+//! the states of the previous iterations. Moves undoing the previous move
+//! are never tried.
+
+use std::hash::Hash;
+use std::time::Instant;
+
+use super::grid2d::Direction;
+use super::grid_traits::Grid;
+use super::heuristic::{Evaluator, Heuristic};
+use super::solver::{is_solvable, state_bytes, Limits, Partial, Solution, Solver};
+use super::solver::{Observer, Progress, PROGRESS_INTERVAL};
+use super::{ErrorKind, PuzzleError, Result};
+
+/// Outcome of a single bounded depth-first search
+enum Bound {
+	Found,
+	/// Smallest f above the bound, `usize::MAX` if nothing was cut off
+	Exceeded(usize),
+}
+
+pub struct IdaStar<G: Grid> {
+	start: G,
+	destination: G,
+	evaluator: Evaluator,
+	time_complex: usize,
+	size_complex: usize,
+	limits: Limits,
+	observer: Option<Box<dyn Observer>>,
+	timer: Instant,
+	/// Lowest h reached and the path to it
+	closest: (usize, Vec<Direction>),
+}
+
+impl<G> IdaStar<G>
+where
+	G: Grid<M = Direction> + Clone + Hash + Eq,
+{
+	pub fn new(start: G, destination: G, heuristic: Heuristic) -> Self {
+		let evaluator = Evaluator::new(heuristic, &destination);
+		Self {
+			start,
+			destination,
+			evaluator,
+			time_complex: 0,
+			size_complex: 0,
+			limits: Limits::default(),
+			observer: None,
+			timer: Instant::now(),
+			closest: (usize::MAX, Vec::new()),
+		}
+	}
+
+	/// Depth-first search below the bound, the path leads to the state
+	fn search(&mut self, state: &mut G, path: &mut Vec<Direction>, bound: usize) -> Result<Bound> {
+		let h = self.evaluator.estimate(state);
+		if h < self.closest.0 {
+			self.closest = (h, path.clone());
+		}
+		let f = path.len() + h;
+		if f > bound {
+			return Ok(Bound::Exceeded(f));
+		}
+		if *state == self.destination {
+			return Ok(Bound::Found);
+		}
+		self.time_complex += 1;
+		self.size_complex = self.size_complex.max(path.len() + 1);
+		let memory = (path.len() + 1) * state_bytes(state);
+		let mut stop = self.limits.exceeded(self.time_complex, memory, &self.timer);
+		if let Some(observer) = self.observer.as_mut() {
+			if stop.is_none() && self.time_complex.is_multiple_of(PROGRESS_INTERVAL) {
+				let progress = Progress {
+					nodes: self.time_complex,
+					open: path.len(),
+					closed: 0,
+					bound,
+					best_h: self.closest.0,
+					elapsed: self.timer.elapsed(),
+				};
+				if !observer.progress(&progress) {
+					stop = Some(ErrorKind::Interrupted);
+				}
+			}
+		}
+		if let Some(limit) = stop {
+			return Err(PuzzleError::Limit(Box::new(Partial {
+				limit,
+				moves: self.closest.1.clone(),
+				estimate: self.closest.0,
+				time_complexity: self.time_complex,
+				size_complexity: self.size_complex,
+				duration: self.timer.elapsed(),
+			})));
+		}
+		let mut next = usize::MAX;
+		for dir in state.possible_moves() {
+			if path.last() == Some(&dir.opposite()) {
+				continue;
+			}
+			state.do_move(&dir)?;
+			path.push(dir);
+			match self.search(state, path, bound)? {
+				Bound::Found => return Ok(Bound::Found),
+				Bound::Exceeded(f) => next = next.min(f),
+			}
+			path.pop();
+			state.undo_move(&dir)?;
+		}
+		Ok(Bound::Exceeded(next))
+	}
+}
+
+impl<G> Solver for IdaStar<G>
+where
+	G: Grid<M = Direction> + Clone + Hash + Eq,
+{
+	/// Finds the shortest path from start to destination
+	fn solve(&mut self) -> Result<Solution> {
+		self.timer = Instant::now();
+		if !is_solvable(&self.start, &self.destination) {
+			return Err(PuzzleError::Puzzle(ErrorKind::Unsolvable));
+		}
+		self.time_complex = 0;
+		self.size_complex = 1;
+		self.closest = (usize::MAX, Vec::new());
+		let mut state = self.start.clone();
+		let mut path = Vec::new();
+		let mut bound = self.evaluator.estimate(&state);
+		loop {
+			match self.search(&mut state, &mut path, bound)? {
+				Bound::Found => {
+					return Ok(Solution {
+						moves: path,
+						time_complexity: self.time_complex,
+						size_complexity: self.size_complex,
+						duration: self.timer.elapsed(),
+						thread_nodes: Vec::new(),
+					})
+				}
+				Bound::Exceeded(usize::MAX) => {
+					return Err(PuzzleError::Puzzle(ErrorKind::Unsolvable))
+				}
+				Bound::Exceeded(next) => bound = next,
+			}
+		}
+	}
+
+	fn set_limits(&mut self, limits: Limits) {
+		self.limits = limits;
+	}
+
+	fn set_observer(&mut self, observer: Box<dyn Observer>) {
+		self.observer = Some(observer);
+	}
+
+	fn get_time_complexity(&self) -> usize {
+		self.time_complex
+	}
+
+	fn get_size_complexity(&self) -> usize {
+		self.size_complex
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::astar::Astar;
+	use super::super::grid2d::Grid2D;
+	use super::super::solver::{Algorithm, Options};
+	use super::super::PuzzleType;
+	use super::*;
+	use std::str::FromStr;
+	use std::time::Duration;
+
+	fn grid(data: Vec<Vec<usize>>) -> Grid2D {
+		let mut grid = Grid2D::new();
+		grid.from_2dvec(data).unwrap();
+		grid
+	}
+
+	#[test]
+	fn algorithms_agree() {
+		let start = grid(vec![vec![3, 2, 6], vec![1, 4, 0], vec![8, 7, 5]]);
+		let goal = grid(PuzzleType::Snail.get_template(3, 3));
+		let optimal = IdaStar::new(start.clone(), goal.clone(), Heuristic::Manhattan)
+			.solve()
+			.unwrap()
+			.len();
+		for name in Algorithm::as_str_array().iter() {
+			let algorithm = Algorithm::from_str(name).unwrap();
+			let mut solver = algorithm.solver(
+				start.clone(),
+				goal.clone(),
+				Heuristic::Manhattan,
+				Options::default(),
+			);
+			let solution = solver.solve().unwrap();
+			assert_eq!(goal, *solution.states(&start).unwrap().last().unwrap());
+			match algorithm.is_optimal() {
+				true => assert_eq!(optimal, solution.len(), "{}", algorithm),
+				false => assert!(optimal <= solution.len()),
+			}
+		}
+		let unsolvable = grid(vec![vec![2, 1, 3], vec![8, 0, 4], vec![7, 6, 5]]);
+		let mut idastar = IdaStar::new(unsolvable, goal, Heuristic::Manhattan);
+		assert!(idastar.solve().is_err());
+	}
+
+	#[test]
+	fn shortest_paths() {
+		let goal = grid(PuzzleType::Snail.get_template(3, 3));
+		let mut idastar = IdaStar::new(goal.clone(), goal.clone(), Heuristic::Manhattan);
+		assert!(idastar.solve().unwrap().is_empty());
+		let starts = vec![
+			vec![vec![3, 2, 6], vec![1, 4, 0], vec![8, 7, 5]],
+			vec![vec![0, 1, 3], vec![8, 2, 4], vec![7, 6, 5]],
+			vec![vec![8, 7, 6], vec![1, 0, 5], vec![2, 3, 4]],
+		];
+		for data in starts {
+			let start = grid(data);
+			let mut astar = Astar::new(start.clone(), goal.clone(), Heuristic::LinearConflict);
+			let optimal = astar.solve().unwrap().len();
+			for heuristic in [Heuristic::Manhattan, Heuristic::LinearConflict].iter() {
+				let mut idastar = IdaStar::new(start.clone(), goal.clone(), *heuristic);
+				let solution = idastar.solve().unwrap();
+				assert_eq!(optimal, solution.len());
+				assert_eq!(goal, *solution.states(&start).unwrap().last().unwrap());
+				// only the current path is held
+				assert!(idastar.get_size_complexity() <= optimal + 1);
+			}
+		}
+		// the first of Korf's instances takes far more than the timeout
+		let start = grid(vec![
+			vec![14, 13, 15, 7],
+			vec![11, 12, 9, 5],
+			vec![6, 0, 2, 1],
+			vec![4, 8, 10, 3],
+		]);
+		let goal = grid(PuzzleType::Lines00.get_template(4, 4));
+		let mut idastar = IdaStar::new(start, goal, Heuristic::Manhattan);
+		idastar.set_limits(Limits {
+			timeout: Some(Duration::from_millis(10)),
+			..Limits::default()
+		});
+		match idastar.solve() {
+			Err(PuzzleError::Limit(partial)) => assert_eq!(ErrorKind::Timeout, partial.limit),
+			other => panic!("expected a timeout, got {:?}", other.map(|s| s.len())),
+		}
+		assert!(idastar.get_time_complexity() > 0);
+	}
+}
@@ -1,13 +1,34 @@
 //! Handles launching appropriate modes based on cmd line input
 //!
 
-use clap::{App, Arg};
-use log::*;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 
 use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
 
+use super::batch::{collect, Job};
+use super::bench::generate;
+use super::challenge::{Challenge, CHALLENGE_ITERATIONS};
+use super::format::Format;
+use super::heuristic::Heuristic;
+use super::logger::{LogConfig, LogFormat};
+use super::scores::default_scores_file;
+use super::solver::{Algorithm, Limits, Options};
 use super::Board;
-use super::Result;
+use super::PuzzleType;
+use super::{ErrorKind, PuzzleError, Result};
+
+const LOG_ENV_HELP: &str = "Base log level (default warn) can be set with NPUZZLE_LOG environment variable, e.g. NPUZZLE_LOG=debug";
+
+const DEFAULT_SIZE: usize = 3;
+const DEFAULT_ITERATIONS: usize = 100;
+/// Benchmarks run on the same puzzles unless asked otherwise
+const DEFAULT_BENCH_SEED: u64 = 42;
 
 #[derive(Debug, Copy, Clone)]
 #[non_exhaustive]
@@ -15,20 +36,19 @@ pub enum PuzzleMode {
     Play,
     Solve,
     Generate,
+    Replay,
+    Verify,
+    Batch,
+    Bench,
+    Analyze,
+    Optimal,
 }
 
 impl PuzzleMode {
     pub fn as_str_array() -> &'static [&'static str] {
-        &["play", "solve", "generate"]
-    }
-
-    pub fn from_str(mode: &str) -> Option<Self> {
-        match mode {
-            "play" => Some(Self::Play),
-            "solve" => Some(Self::Solve),
-            "generate" => Some(Self::Generate),
-            _ => None,
-        }
+        &[
+            "play", "solve", "generate", "replay", "verify", "batch", "bench", "analyze", "optimal",
+        ]
     }
 
     pub fn as_str(&self) -> &'static str {
@@ -36,7 +56,31 @@ impl PuzzleMode {
             Self::Play => "play",
             Self::Solve => "solve",
             Self::Generate => "generate",
-            _ => "unknown",
+            Self::Replay => "replay",
+            Self::Verify => "verify",
+            Self::Batch => "batch",
+            Self::Bench => "bench",
+            Self::Analyze => "analyze",
+            Self::Optimal => "optimal",
+        }
+    }
+}
+
+impl FromStr for PuzzleMode {
+    type Err = PuzzleError;
+
+    fn from_str(mode: &str) -> Result<Self> {
+        match mode {
+            "play" => Ok(Self::Play),
+            "solve" => Ok(Self::Solve),
+            "generate" => Ok(Self::Generate),
+            "replay" => Ok(Self::Replay),
+            "verify" => Ok(Self::Verify),
+            "batch" => Ok(Self::Batch),
+            "bench" => Ok(Self::Bench),
+            "analyze" => Ok(Self::Analyze),
+            "optimal" => Ok(Self::Optimal),
+            _ => Err(PuzzleError::Puzzle(ErrorKind::InvalidMode)),
         }
     }
 }
@@ -51,108 +95,881 @@ impl fmt::Display for PuzzleMode {
 pub struct Launcher {
     mode: PuzzleMode,
     size: Option<usize>,
+    height: Option<usize>,
     iters: Option<usize>,
     input_file: Option<String>,
+    state: Option<String>,
+    puzzletype: PuzzleType,
+    /// Whether `--goal` was given, overriding the goal of JSON puzzles
+    goal_given: bool,
+    seed: Option<u64>,
+    scores_file: String,
+    challenge: Option<Challenge>,
+    algorithm: Algorithm,
+    heuristic: Heuristic,
+    algorithms: Vec<Algorithm>,
+    heuristics: Vec<Heuristic>,
+    count: usize,
+    repeat: usize,
+    moves: Option<String>,
+    moves_file: Option<String>,
+    verify: bool,
+    input_format: Format,
+    output_format: Format,
+    batch_path: Option<String>,
+    limits: Limits,
+    jobs: usize,
+    options: Options,
+    optimize: bool,
+    list: usize,
+    csv: bool,
+    log_config: LogConfig,
 }
 
 impl Launcher {
-    pub fn new() -> Self {
+    /// Parses the command line, help and version requests exit right away
+    pub fn new() -> Result<Self> {
         let args = App::new(crate_name!())
             .author(crate_authors!())
             .version(crate_version!())
             .about("N-puzzle solver. Project within ecole 42 curriculum at Hive Helsinki")
-            .arg(
-                Arg::with_name("size")
-                    .short("n")
-                    .takes_value(true)
-                    .conflicts_with("infile")
-                    .value_name("SIZE")
-                    .required_unless("infile")
-                    .conflicts_with("infile")
-                    .help("Size of the puzzle"),
+            .after_help(LOG_ENV_HELP)
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .setting(AppSettings::VersionlessSubcommands)
+            .args(&log_args())
+            .subcommand(
+                SubCommand::with_name(PuzzleMode::Solve.as_str())
+                    .about("Finds the shortest solution of a puzzle")
+                    .args(&board_args(true))
+                    .arg(algorithm_arg())
+                    .arg(heuristic_arg())
+                    .args(&limit_args())
+                    .arg(
+                        Arg::with_name("threads")
+                            .long("threads")
+                            .value_name("N")
+                            .takes_value(true)
+                            .default_value("0")
+                            .validator(|v| match v.parse::<usize>() {
+                                Ok(_) => Ok(()),
+                                Err(_) => Err(String::from("threads must be a number")),
+                            })
+                            .help("Threads of parallel-ida-star, 0 for one per CPU core"),
+                    )
+                    .arg(
+                        Arg::with_name("node_budget")
+                            .long("node-budget")
+                            .value_name("NUM")
+                            .takes_value(true)
+                            .validator(is_positive)
+                            .help("States held by sma-star, its solution has to fit in"),
+                    )
+                    .arg(
+                        Arg::with_name("beam_width")
+                            .long("beam-width")
+                            .value_name("NUM")
+                            .takes_value(true)
+                            .validator(is_positive)
+                            .help("States kept at each depth by beam [default: 500]"),
+                    )
+                    .arg(
+                        Arg::with_name("optimize")
+                            .long("optimize")
+                            .help("Shortens the solution of the non-optimal algorithms"),
+                    )
+                    .arg(
+                        Arg::with_name("verify")
+                            .long("verify")
+                            .help("Independently verifies the solution, always on in debug builds"),
+                    )
+                    .arg(output_format_arg()),
+            )
+            .subcommand(
+                SubCommand::with_name(PuzzleMode::Generate.as_str())
+                    .about("Prints a random puzzle in the subject's format")
+                    .args(&shuffle_args())
+                    .arg(size_arg().required(true))
+                    .arg(goal_arg())
+                    .arg(output_format_arg()),
+            )
+            .subcommand(
+                SubCommand::with_name(PuzzleMode::Play.as_str())
+                    .about("Interactive game with a local high-score table")
+                    .args(&board_args(false))
+                    .arg(
+                        Arg::with_name("scores")
+                            .long("scores")
+                            .value_name("FILE")
+                            .takes_value(true)
+                            .help("High-score file, defaults to ~/.npuzzle_scores"),
+                    )
+                    .arg(
+                        Arg::with_name("challenge")
+                            .long("challenge")
+                            .value_name("CODE")
+                            .takes_value(true)
+                            .min_values(0)
+                            .conflicts_with_all(&["infile", "state", "seed"])
+                            .validator(|v| {
+                                Challenge::from_code(&v)
+                                    .map(|_| ())
+                                    .map_err(|e| e.to_string())
+                            })
+                            .help("Same puzzle for everybody, the code defaults to today's date"),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name(PuzzleMode::Replay.as_str())
+                    .about("Applies a move sequence to a puzzle and reports the result")
+                    .args(&board_args(true))
+                    .args(&moves_args()),
+            )
+            .subcommand(
+                SubCommand::with_name(PuzzleMode::Batch.as_str())
+                    .about("Solves every puzzle of a directory or a file and reports the results")
+                    .arg(
+                        Arg::with_name("path")
+                            .value_name("PATH")
+                            .required(true)
+                            .help("Directory of puzzle files or a file with several puzzles"),
+                    )
+                    .arg(goal_arg())
+                    .arg(
+                        Arg::with_name("input_format")
+                            .long("input-format")
+                            .value_name("FORMAT")
+                            .takes_value(true)
+                            .default_value("text")
+                            .possible_values(Format::as_str_array())
+                            .help("Format of the puzzle files"),
+                    )
+                    .arg(algorithm_arg())
+                    .arg(heuristic_arg())
+                    .args(&limit_args())
+                    .arg(
+                        Arg::with_name("jobs")
+                            .short("j")
+                            .long("jobs")
+                            .value_name("N")
+                            .takes_value(true)
+                            .default_value("1")
+                            .validator(|v| match v.parse::<usize>() {
+                                Ok(_) => Ok(()),
+                                Err(_) => Err(String::from("jobs must be a number")),
+                            })
+                            .help("Puzzles solved in parallel, 0 for one per CPU core"),
+                    )
+                    .arg(
+                        Arg::with_name("csv")
+                            .long("csv")
+                            .help("Prints the report as CSV instead of a table"),
+                    ),
             )
-            .arg(
-                Arg::with_name("iterations")
-                    .short("i")
-                    .value_name("NUM")
-                    .long("iterations")
-                    .takes_value(true)
-                    .required_unless("infile")
-                    .conflicts_with("infile")
-                    .help("Shuffle iterations"),
+            .subcommand(
+                SubCommand::with_name(PuzzleMode::Bench.as_str())
+                    .about("Compares algorithms and heuristics over a set of puzzles")
+                    .arg(
+                        Arg::with_name("infile")
+                            .short("f")
+                            .long("file")
+                            .value_name("PATH")
+                            .takes_value(true)
+                            .help("Directory of puzzle files or a file with several puzzles"),
+                    )
+                    .arg(
+                        size_arg()
+                            .conflicts_with("infile")
+                            .help("Size of the generated puzzles [default: 3]"),
+                    )
+                    .args(&shuffle_args())
+                    .arg(
+                        Arg::with_name("count")
+                            .long("count")
+                            .value_name("NUM")
+                            .takes_value(true)
+                            .default_value("10")
+                            .validator(is_positive)
+                            .help("Number of generated puzzles"),
+                    )
+                    .arg(goal_arg())
+                    .arg(
+                        Arg::with_name("input_format")
+                            .long("input-format")
+                            .value_name("FORMAT")
+                            .takes_value(true)
+                            .default_value("text")
+                            .possible_values(Format::as_str_array())
+                            .help("Format of the puzzle files"),
+                    )
+                    .arg(
+                        Arg::with_name("algorithms")
+                            .long("algorithms")
+                            .value_name("LIST")
+                            .takes_value(true)
+                            .use_delimiter(true)
+                            .possible_values(Algorithm::as_str_array())
+                            .help("Comma separated algorithms to compare [default: all]"),
+                    )
+                    .arg(
+                        Arg::with_name("heuristics")
+                            .long("heuristics")
+                            .value_name("LIST")
+                            .takes_value(true)
+                            .use_delimiter(true)
+                            .possible_values(Heuristic::as_str_array())
+                            .help("Comma separated heuristics to compare [default: all]"),
+                    )
+                    .arg(
+                        Arg::with_name("repeat")
+                            .long("repeat")
+                            .value_name("NUM")
+                            .takes_value(true)
+                            .default_value("1")
+                            .validator(is_positive)
+                            .help("Runs of every puzzle, timing is taken over all of them"),
+                    )
+                    .args(&limit_args())
+                    .arg(
+                        Arg::with_name("csv")
+                            .long("csv")
+                            .help("Prints the report as CSV instead of a table"),
+                    )
+                    .arg(
+                        Arg::with_name("json")
+                            .long("json")
+                            .conflicts_with("csv")
+                            .help("Prints the report as JSON instead of a table"),
+                    ),
             )
-            .arg(
-                Arg::with_name("mode")
-                    .long("mode")
-                    .value_name("MODE")
-                    .short("m")
-                    .default_value("solve")
-                    .possible_values(PuzzleMode::as_str_array())
-                    .takes_value(true)
-                    .help("Play mode"),
+            .subcommand(
+                SubCommand::with_name(PuzzleMode::Verify.as_str())
+                    .about("Checks that a move sequence is a valid solution of a puzzle")
+                    .args(&board_args(true))
+                    .args(&moves_args()),
             )
-            .arg(
-                Arg::with_name("infile")
-                    .short("f")
-                    .long("file")
-                    .takes_value(true)
-                    .value_name("FILE")
-                    .required_unless("size")
-                    .conflicts_with("size")
-                    .help("Reads the intial state from given file"),
+            .subcommand(
+                SubCommand::with_name(PuzzleMode::Analyze.as_str())
+                    .about("Enumerates every state of a small board with its distance to the goal")
+                    .arg(size_arg().required(true).help("Width of the board"))
+                    .arg(
+                        Arg::with_name("height")
+                            .long("height")
+                            .value_name("ROWS")
+                            .takes_value(true)
+                            .validator(|v| match v.parse::<usize>() {
+                                Ok(n) if n >= 2 => Ok(()),
+                                _ => Err(String::from("height must be a number of at least 2")),
+                            })
+                            .help(
+                                "Height of the board, e.g. 2 for 2xN boards [default: the width]",
+                            ),
+                    )
+                    .arg(goal_arg())
+                    .arg(output_format_arg()),
             )
-            .get_matches();
+            .subcommand(
+                SubCommand::with_name(PuzzleMode::Optimal.as_str())
+                    .about("Counts the shortest solutions of a puzzle and lists some of them")
+                    .args(&board_args(true))
+                    .arg(heuristic_arg())
+                    .args(&limit_args())
+                    .arg(
+                        Arg::with_name("list")
+                            .long("list")
+                            .value_name("NUM")
+                            .takes_value(true)
+                            .default_value("0")
+                            .validator(|v| match v.parse::<usize>() {
+                                Ok(_) => Ok(()),
+                                Err(_) => Err(String::from("list must be a number")),
+                            })
+                            .help("Prints the first NUM shortest solutions"),
+                    )
+                    .arg(output_format_arg()),
+            )
+            .get_matches_safe()
+            .map_err(|err| match err.kind {
+                clap::ErrorKind::HelpDisplayed | clap::ErrorKind::VersionDisplayed => err.exit(),
+                _ => err,
+            })?;
+        // Will panic if mode is not supported
+        // should be handled by clap's subcommands before this happens tho
+        let log_config = LogConfig::from_verbosity(
+            args.occurrences_of("verbose"),
+            args.occurrences_of("quiet"),
+            LogFormat::from_str(args.value_of("log_format").unwrap()).unwrap(),
+            args.value_of("log_file").map(String::from),
+        );
+        let (name, sub_args) = args.subcommand();
+        let mode = PuzzleMode::from_str(name).unwrap();
+        let args = sub_args.unwrap();
+        let challenge = if args.is_present("challenge") {
+            match args.value_of("challenge") {
+                Some(code) => Some(Challenge::from_code(code)?),
+                None => Some(Challenge::today()),
+            }
+        } else {
+            None
+        };
+        let input_file = args.value_of("infile").map(String::from);
+        let state = args.value_of("state").map(String::from);
+        let batch_path = args.value_of("path").map(String::from);
+        let generated = input_file.is_none() && state.is_none() && batch_path.is_none();
         let size = if args.is_present("size") {
-            Some(value_t_or_exit!(args, "size", usize))
+            Some(value_t!(args, "size", usize)?)
+        } else if generated {
+            Some(DEFAULT_SIZE)
         } else {
             None
         };
         let iters = if args.is_present("iterations") {
-            Some(value_t_or_exit!(args, "iterations", usize))
+            Some(value_t!(args, "iterations", usize)?)
+        } else if challenge.is_some() {
+            Some(CHALLENGE_ITERATIONS)
+        } else if generated {
+            Some(DEFAULT_ITERATIONS)
         } else {
             None
         };
-        let input_file = if args.is_present("infile") {
-            Some(String::from(args.value_of("infile").unwrap()))
+        let puzzletype = PuzzleType::from_str(args.value_of("goal").unwrap()).unwrap();
+        let algorithm = match args.value_of("algorithm") {
+            Some(algorithm) => Algorithm::from_str(algorithm).unwrap(),
+            None => Algorithm::Astar,
+        };
+        check_algorithm_options(args, algorithm)?;
+        let heuristic = match args.value_of("heuristic") {
+            Some(heuristic) => Heuristic::from_str(heuristic).unwrap(),
+            None => Heuristic::Manhattan,
+        };
+        let algorithms = args
+            .values_of("algorithms")
+            .map_or(Algorithm::as_str_array().to_vec(), |values| {
+                values.collect()
+            })
+            .iter()
+            .map(|algorithm| Algorithm::from_str(algorithm).unwrap())
+            .collect();
+        let heuristics = args
+            .values_of("heuristics")
+            .map_or(Heuristic::as_str_array().to_vec(), |values| {
+                values.collect()
+            })
+            .iter()
+            .map(|heuristic| Heuristic::from_str(heuristic).unwrap())
+            .collect();
+        // Generated boards always get a seed so the game can be recorded and replayed
+        let seed = if args.is_present("seed") {
+            Some(value_t!(args, "seed", u64)?)
+        } else if let Some(challenge) = &challenge {
+            Some(challenge.seed(size.unwrap(), iters.unwrap(), puzzletype))
+        } else if size.is_some() && matches!(mode, PuzzleMode::Bench) {
+            Some(DEFAULT_BENCH_SEED)
+        } else if size.is_some() {
+            Some(thread_rng().gen())
         } else {
             None
         };
-        // Will panic if mode is not supported
-        // should be handled by clap's default values before this happens tho
-        let mode = PuzzleMode::from_str(args.value_of("mode").unwrap()).unwrap();
-        Self {
+        let limits = Limits {
+            timeout: match args.value_of("timeout") {
+                Some(_) => Some(Duration::from_secs_f64(value_t!(args, "timeout", f64)?)),
+                None => None,
+            },
+            max_nodes: match args.value_of("max_nodes") {
+                Some(_) => Some(value_t!(args, "max_nodes", usize)?),
+                None => None,
+            },
+            max_memory: args.value_of("max_memory").and_then(parse_memory),
+        };
+        let jobs = match value_t!(args, "jobs", usize).unwrap_or(1) {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            jobs => jobs,
+        };
+        let scores_file = match args.value_of("scores") {
+            Some(file) => String::from(file),
+            None => default_scores_file(),
+        };
+        Ok(Self {
             mode,
             size,
+            height: value_t!(args, "height", usize).ok(),
             iters,
             input_file,
-        }
+            state,
+            puzzletype,
+            goal_given: args.occurrences_of("goal") > 0,
+            seed,
+            scores_file,
+            challenge,
+            algorithm,
+            heuristic,
+            algorithms,
+            heuristics,
+            count: value_t!(args, "count", usize).unwrap_or(1),
+            repeat: value_t!(args, "repeat", usize).unwrap_or(1),
+            moves: args.value_of("moves").map(String::from),
+            moves_file: args.value_of("moves_file").map(String::from),
+            verify: args.is_present("verify") || cfg!(debug_assertions),
+            input_format: format_of(args, "input_format"),
+            output_format: match args.is_present("json") {
+                true => Format::Json,
+                false => format_of(args, "output_format"),
+            },
+            batch_path,
+            limits,
+            jobs,
+            options: Options {
+                threads: value_t!(args, "threads", usize).unwrap_or(0),
+                node_budget: value_t!(args, "node_budget", usize).ok(),
+                beam_width: value_t!(args, "beam_width", usize).ok(),
+            },
+            optimize: args.is_present("optimize"),
+            list: value_t!(args, "list", usize).unwrap_or(0),
+            csv: args.is_present("csv"),
+            log_config,
+        })
     }
 
     /// Gets game board based on parsed arguments
-    /// panics if both size and input_file are None
+    /// panics if size, input_file and state are all None
     /// or if size is Some and iterations is None
     pub fn get_board(&self) -> Result<Board> {
-        if self.input_file.is_some() {
-            Board::from_file(self.input_file.as_ref().unwrap())
+        if let Some(input_file) = &self.input_file {
+            let mut board = Board::from_file(input_file, self.input_format)?;
+            if let Some(puzzletype) = self.get_goal_override() {
+                board.set_puzzletype(puzzletype);
+            }
+            Ok(board)
+        } else if let Some(state) = &self.state {
+            let mut board = Board::from_str(state).map_err(|e| match e {
+                PuzzleError::Parse(mut err) => {
+                    err.file = Some(String::from("--state"));
+                    PuzzleError::Parse(err)
+                }
+                e => e,
+            })?;
+            board.set_puzzletype(self.puzzletype);
+            Ok(board)
         } else {
             let n = self.size.unwrap();
-            let mut board = Board::new(n, n);
-            board.shuffle(self.iters.unwrap());
+            let mut board = Board::with_type(n, n, self.puzzletype);
+            let mut rng = StdRng::seed_from_u64(self.seed.unwrap());
+            board.shuffle(self.iters.unwrap(), &mut rng);
             Ok(board)
         }
     }
 
+    /// Gets every board of the input file, or the single generated one
+    pub fn get_boards(&self) -> Result<Vec<Result<Board>>> {
+        match &self.input_file {
+            Some(input_file) => {
+                let mut boards = Board::all_from_file(input_file, self.input_format)?;
+                if let Some(puzzletype) = self.get_goal_override() {
+                    for board in boards.iter_mut().flatten() {
+                        board.set_puzzletype(puzzletype);
+                    }
+                }
+                Ok(boards)
+            }
+            None => Ok(vec![self.get_board()]),
+        }
+    }
+
+    /// Puzzles of the benchmark, read from the path or generated from the seed
+    pub fn get_bench_jobs(&self) -> Result<Vec<Job>> {
+        match &self.input_file {
+            Some(path) => collect(path, self.input_format, self.get_goal_override()),
+            None => Ok(generate(
+                self.size.unwrap(),
+                self.count,
+                self.iters.unwrap(),
+                self.seed.unwrap(),
+                self.puzzletype,
+            )),
+        }
+    }
+
     pub fn get_mode(&self) -> PuzzleMode {
         self.mode
     }
+
+    /// Width and height of the analyzed board, panics without a size
+    pub fn get_dimensions(&self) -> (usize, usize) {
+        let width = self.size.unwrap();
+        (width, self.height.unwrap_or(width))
+    }
+
+    pub fn get_seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn get_scores_file(&self) -> &str {
+        &self.scores_file
+    }
+
+    pub fn get_challenge(&self) -> Option<&Challenge> {
+        self.challenge.as_ref()
+    }
+
+    pub fn get_algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    pub fn get_heuristic(&self) -> Heuristic {
+        self.heuristic
+    }
+
+    pub fn get_algorithms(&self) -> &[Algorithm] {
+        &self.algorithms
+    }
+
+    pub fn get_heuristics(&self) -> &[Heuristic] {
+        &self.heuristics
+    }
+
+    pub fn get_repeat(&self) -> usize {
+        self.repeat
+    }
+
+    pub fn get_log_config(&self) -> &LogConfig {
+        &self.log_config
+    }
+
+    pub fn get_verify(&self) -> bool {
+        self.verify
+    }
+
+    pub fn get_output_format(&self) -> Format {
+        self.output_format
+    }
+
+    pub fn get_input_format(&self) -> Format {
+        self.input_format
+    }
+
+    pub fn get_goal(&self) -> PuzzleType {
+        self.puzzletype
+    }
+
+    /// Goal of the puzzles read, none to keep the one of each JSON puzzle
+    pub fn get_goal_override(&self) -> Option<PuzzleType> {
+        match self.goal_given {
+            true => Some(self.puzzletype),
+            false => None,
+        }
+    }
+
+    /// Path of the batch mode, panics in other modes
+    pub fn get_batch_path(&self) -> &str {
+        self.batch_path.as_ref().unwrap()
+    }
+
+    pub fn get_limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub fn get_jobs(&self) -> usize {
+        self.jobs
+    }
+
+    /// Settings of the selected algorithm
+    pub fn get_options(&self) -> Options {
+        self.options
+    }
+
+    pub fn get_optimize(&self) -> bool {
+        self.optimize
+    }
+
+    /// Shortest solutions to print in the optimal mode
+    pub fn get_list(&self) -> usize {
+        self.list
+    }
+
+    pub fn get_csv(&self) -> bool {
+        self.csv
+    }
+
+    /// Move sequence for replay and verify from argument, file or stdin
+    pub fn get_moves(&self) -> Result<String> {
+        if let Some(moves) = &self.moves {
+            Ok(moves.clone())
+        } else if let Some(file) = &self.moves_file {
+            Ok(fs::read_to_string(file)?)
+        } else if self.input_file.as_deref() == Some("-") {
+            Err(PuzzleError::Custom(String::from(
+                "Puzzle and moves cannot both be read from stdin, use --moves or --moves-file",
+            )))
+        } else {
+            let mut moves = String::new();
+            io::stdin().read_to_string(&mut moves)?;
+            Ok(moves)
+        }
+    }
+}
+
+/// Logging options, accepted before or after the subcommand
+fn log_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("verbose")
+            .short("v")
+            .long("verbose")
+            .multiple(true)
+            .global(true)
+            .help("More log output, repeat for more (-vv)"),
+        Arg::with_name("quiet")
+            .short("q")
+            .long("quiet")
+            .multiple(true)
+            .global(true)
+            .help("Less log output, repeat for less (-qq)"),
+        Arg::with_name("log_format")
+            .long("log-format")
+            .value_name("FORMAT")
+            .default_value("text")
+            .possible_values(LogFormat::as_str_array())
+            .takes_value(true)
+            .global(true)
+            .help("Log line format, json gives one object per line"),
+        Arg::with_name("log_file")
+            .long("log-file")
+            .value_name("FILE")
+            .takes_value(true)
+            .global(true)
+            .help("Appends logs to given file instead of stderr"),
+    ]
+}
+
+fn size_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("size")
+        .short("n")
+        .takes_value(true)
+        .value_name("SIZE")
+        .validator(|v| match v.parse::<usize>() {
+            Ok(n) if n >= 2 => Ok(()),
+            _ => Err(String::from("size must be a number of at least 2")),
+        })
+        .help("Size of the puzzle")
+}
+
+fn heuristic_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("heuristic")
+        .long("heuristic")
+        .value_name("HEURISTIC")
+        .default_value("manhattan")
+        .possible_values(Heuristic::as_str_array())
+        .takes_value(true)
+        .help("Heuristic function used by the solver")
+}
+
+fn algorithm_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("algorithm")
+        .long("algorithm")
+        .value_name("ALGORITHM")
+        .default_value("astar")
+        .possible_values(Algorithm::as_str_array())
+        .takes_value(true)
+        .help("Search algorithm, greedy is fast but its solutions are not the shortest")
+}
+
+/// Limits of a search, reaching one stops it with the closest state found
+fn limit_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("timeout")
+            .long("timeout")
+            .value_name("SECONDS")
+            .takes_value(true)
+            .validator(|v| match v.parse::<f64>() {
+                Ok(t) if t > 0.0 => Ok(()),
+                _ => Err(String::from("timeout must be a positive number of seconds")),
+            })
+            .help("Stops a search after given time"),
+        Arg::with_name("max_nodes")
+            .long("max-nodes")
+            .value_name("NUM")
+            .takes_value(true)
+            .validator(is_positive)
+            .help("Stops a search after expanding given number of states"),
+        Arg::with_name("max_memory")
+            .long("max-memory")
+            .value_name("SIZE")
+            .takes_value(true)
+            .validator(|v| match parse_memory(&v) {
+                Some(_) => Ok(()),
+                None => Err(String::from("memory must be a size such as 512M or 2G")),
+            })
+            .help("Stops a search holding about given memory, in bytes or with K, M or G"),
+    ]
+}
+
+/// Memory size in bytes, e.g. "4096", "512K", "512M" or "2G" (binary units)
+fn parse_memory(size: &str) -> Option<usize> {
+    let size = size.trim();
+    let (number, unit) = match size.char_indices().last()? {
+        (i, c) if c.is_ascii_alphabetic() => (&size[..i], c.to_ascii_uppercase()),
+        _ => (size, 'B'),
+    };
+    let shift = match unit {
+        'B' => 0,
+        'K' => 10,
+        'M' => 20,
+        'G' => 30,
+        _ => return None,
+    };
+    match number.parse::<usize>() {
+        Ok(n) if n > 0 => n.checked_mul(1 << shift),
+        _ => None,
+    }
+}
+
+fn is_positive(v: String) -> std::result::Result<(), String> {
+    match v.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(()),
+        _ => Err(String::from("expected a positive number")),
+    }
+}
+
+fn goal_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("goal")
+        .short("g")
+        .long("goal")
+        .value_name("TYPE")
+        .default_value("snail")
+        .possible_values(PuzzleType::as_str_array())
+        .takes_value(true)
+        .help("Expected solution of the puzzle")
+}
+
+/// Options of one algorithm are an error with the others rather than ignored
+fn check_algorithm_options(args: &ArgMatches, algorithm: Algorithm) -> Result<()> {
+    let owners = [
+        ("threads", Algorithm::ParallelIdaStar),
+        ("node_budget", Algorithm::SmaStar),
+        ("beam_width", Algorithm::Beam),
+    ];
+    for &(name, owner) in owners.iter() {
+        if args.occurrences_of(name) > 0 && algorithm != owner {
+            return Err(clap::Error::with_description(
+                &format!(
+                    "--{} is only used by --algorithm {}",
+                    name.replace('_', "-"),
+                    owner
+                ),
+                clap::ErrorKind::ArgumentConflict,
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Options of a randomly generated board
+fn shuffle_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("iterations")
+            .short("i")
+            .value_name("NUM")
+            .long("iterations")
+            .takes_value(true)
+            .conflicts_with_all(&["infile", "state"])
+            .help("Shuffle iterations [default: 100]"),
+        Arg::with_name("seed")
+            .short("s")
+            .long("seed")
+            .value_name("SEED")
+            .takes_value(true)
+            .conflicts_with_all(&["infile", "state"])
+            .help("Seed for the shuffle, random if not given"),
+    ]
+}
+
+/// Options selecting the board - read from a file or randomly generated
+fn board_args<'a, 'b>(required: bool) -> Vec<Arg<'a, 'b>> {
+    let mut size = size_arg().conflicts_with_all(&["infile", "state"]);
+    let mut infile = Arg::with_name("infile")
+        .short("f")
+        .long("file")
+        .takes_value(true)
+        .value_name("FILE")
+        .conflicts_with_all(&["size", "state"])
+        .help("Reads the intial state from given file, - for stdin");
+    let mut state = Arg::with_name("state")
+        .long("state")
+        .takes_value(true)
+        .value_name("STATE")
+        .allow_hyphen_values(true)
+        .help("Initial state in compact notation, e.g. 3:1,2,3/8,0,4/7,6,5");
+    if required {
+        size = size.required_unless_one(&["infile", "state"]);
+        infile = infile.required_unless_one(&["size", "state"]);
+        state = state.required_unless_one(&["size", "infile"]);
+    } else {
+        size = size.help("Size of the puzzle [default: 3]");
+    }
+    let input_format = Arg::with_name("input_format")
+        .long("input-format")
+        .value_name("FORMAT")
+        .takes_value(true)
+        .default_value("text")
+        .possible_values(Format::as_str_array())
+        .help("Format of the input file");
+    let mut args = vec![size, infile, state, input_format, goal_arg()];
+    args.extend(shuffle_args());
+    args
+}
+
+fn output_format_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("output_format")
+        .long("output-format")
+        .value_name("FORMAT")
+        .takes_value(true)
+        .default_value("text")
+        .possible_values(Format::as_str_array())
+        .help("Format of the output, see the JSON schema in the README")
+}
+
+/// Format option, text if the subcommand does not have it
+fn format_of(args: &ArgMatches, name: &str) -> Format {
+    args.value_of(name)
+        .map_or(Format::Text, |format| Format::from_str(format).unwrap())
+}
+
+/// Move sequence options of replay and verify
+fn moves_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("moves")
+            .long("moves")
+            .value_name("MOVES")
+            .takes_value(true)
+            .conflicts_with("moves_file")
+            .help("Move sequence, e.g. \"ruldlu\", read from stdin if not given"),
+        Arg::with_name("moves_file")
+            .long("moves-file")
+            .value_name("FILE")
+            .takes_value(true)
+            .help("Reads the move sequence from given file"),
+    ]
 }
 
 impl fmt::Display for Launcher {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "N-puzzle launcher:\nMode: {}\n", self.mode)?;
-        write!(f, "Size: {:?}\n", self.size)?;
-        write!(f, "Iterations: {:?}\n", self.iters)?;
-        write!(f, "Iput File: {:?}\n", self.input_file)?;
+        writeln!(f, "N-puzzle launcher:\nMode: {}", self.mode)?;
+        writeln!(f, "Size: {:?}", self.size)?;
+        writeln!(f, "Iterations: {:?}", self.iters)?;
+        writeln!(f, "Iput File: {:?}", self.input_file)?;
+        writeln!(f, "State: {:?}", self.state)?;
+        writeln!(f, "Goal: {}", self.puzzletype)?;
+        writeln!(f, "Seed: {:?}", self.seed)?;
+        if let Some(challenge) = &self.challenge {
+            writeln!(f, "Challenge: {}", challenge.get_code())?;
+        }
+        writeln!(f, "Algorithm: {}", self.algorithm)?;
+        writeln!(f, "Heuristic: {}", self.heuristic)?;
+        writeln!(f, "Input format: {}", self.input_format)?;
+        writeln!(f, "Output format: {}", self.output_format)?;
+        if let Some(path) = &self.batch_path {
+            writeln!(f, "Batch: {} ({} jobs)", path, self.jobs)?;
+        }
+        writeln!(f, "Limits: {:?}", self.limits)?;
         Ok(())
     }
 }
@@ -1,29 +1,195 @@
 use chrono::{DateTime, Local};
 use log::*;
 
-const LOGGER: Logger = Logger;
+use std::env;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use super::{ErrorKind, PuzzleError, Result};
+
+/// Environment variable setting the base log level, e.g. `NPUZZLE_LOG=debug`
+pub const LOG_ENV: &str = "NPUZZLE_LOG";
+
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Warn;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl LogFormat {
+    pub fn as_str_array() -> &'static [&'static str] {
+        &["text", "json"]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Text => "text",
+            Self::Json => "json",
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = PuzzleError;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(PuzzleError::Puzzle(ErrorKind::InvalidInput)),
+        }
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Where, what and how to log
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    pub level: LevelFilter,
+    pub format: LogFormat,
+    /// Log file, stderr if None
+    pub file: Option<String>,
+}
+
+impl LogConfig {
+    /// Base level from the environment shifted by `-v` / `-q` counts
+    pub fn from_verbosity(
+        verbose: u64,
+        quiet: u64,
+        format: LogFormat,
+        file: Option<String>,
+    ) -> Self {
+        let base = env::var(LOG_ENV)
+            .ok()
+            .and_then(|level| LevelFilter::from_str(&level).ok())
+            .unwrap_or(DEFAULT_LEVEL);
+        let levels = [
+            LevelFilter::Off,
+            LevelFilter::Error,
+            LevelFilter::Warn,
+            LevelFilter::Info,
+            LevelFilter::Debug,
+            LevelFilter::Trace,
+        ];
+        let index = (base as i64 + verbose as i64 - quiet as i64).clamp(0, 5);
+        Self {
+            level: levels[index as usize],
+            format,
+            file,
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: DEFAULT_LEVEL,
+            format: LogFormat::Text,
+            file: None,
+        }
+    }
+}
+
+struct Logger {
+    level: LevelFilter,
+    format: LogFormat,
+    output: Mutex<Box<dyn Write + Send>>,
+}
 
-struct Logger;
 impl log::Log for Logger {
-    fn enabled(&self, _metadata: &Metadata) -> bool {
-        true
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
     }
 
     fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
         let now: DateTime<Local> = Local::now();
-        println!(
-            "{} [{}] {}",
-            now.format("%Y-%m-%d %H:%M:%S%.6f"),
-            record.level(),
-            record.args()
-        );
+        let line = match self.format {
+            LogFormat::Text => format!(
+                "{} [{}] {}",
+                now.format("%Y-%m-%d %H:%M:%S%.6f"),
+                record.level(),
+                record.args()
+            ),
+            LogFormat::Json => format!(
+                "{{\"time\":\"{}\",\"level\":\"{}\",\"target\":\"{}\",\"message\":\"{}\"}}",
+                now.to_rfc3339(),
+                record.level(),
+                json_escape(record.target()),
+                json_escape(&record.args().to_string())
+            ),
+        };
+        if let Ok(mut output) = self.output.lock() {
+            // Nowhere to report failing logger
+            let _ = writeln!(output, "{}", line);
+        }
     }
 
-    fn flush(&self) {}
+    fn flush(&self) {
+        if let Ok(mut output) = self.output.lock() {
+            let _ = output.flush();
+        }
+    }
 }
 
-pub fn init_logger() {
-    if set_logger(&LOGGER).is_ok() {
-        log::set_max_level(LevelFilter::Debug);
+fn json_escape(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Logs go to stderr or a file, never to stdout where the program's output is
+pub fn init_logger(config: &LogConfig) -> Result<()> {
+    let output: Box<dyn Write + Send> = match &config.file {
+        Some(file) => Box::new(OpenOptions::new().create(true).append(true).open(file)?),
+        None => Box::new(io::stderr()),
+    };
+    let logger = Box::leak(Box::new(Logger {
+        level: config.level,
+        format: config.format,
+        output: Mutex::new(output),
+    }));
+    if set_logger(logger).is_ok() {
+        log::set_max_level(config.level);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escaping() {
+        assert_eq!(
+            "N-puzzle \\\"launcher\\\":\\nMode: solve\\t\\\\",
+            json_escape("N-puzzle \"launcher\":\nMode: solve\t\\")
+        );
+        let config = LogConfig::from_verbosity(10, 0, LogFormat::Text, None);
+        assert_eq!(LevelFilter::Trace, config.level);
+        let config = LogConfig::from_verbosity(0, 10, LogFormat::Json, None);
+        assert_eq!(LevelFilter::Off, config.level);
     }
 }
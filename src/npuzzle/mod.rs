@@ -1,15 +1,40 @@
+pub use astar::Astar;
 pub use board::Board;
+pub use challenge::Challenge;
 pub use generator::{Generator, PuzzleType};
+pub use heuristic::Heuristic;
 pub use launcher::Launcher;
-pub use logger::init_logger;
-pub use status::{ErrorKind, PuzzleError, Result};
+pub use logger::{init_logger, LogConfig};
+pub use scores::{Score, ScoreBoard};
+pub use solver::{Algorithm, Solution, Solver};
+pub use status::{ErrorKind, ParseError, PuzzleError, Result};
 
+pub mod anytime;
 pub mod astar;
+pub mod batch;
+pub mod beam;
+pub mod bench;
 pub mod board;
+pub mod challenge;
+pub mod constructive;
+pub mod enumerate;
+pub mod format;
 pub mod generator;
 pub mod grid2d;
 pub mod grid_traits;
+pub mod heuristic;
+pub mod idastar;
 pub mod launcher;
 pub mod logger;
+pub mod optimal;
+pub mod optimize;
+pub mod parallel;
+pub mod progress;
+pub mod rank;
+pub mod replay;
+pub mod scores;
+pub mod smastar;
+pub mod solver;
 pub mod status;
 pub mod utils;
+pub mod verify;
@@ -0,0 +1,297 @@
+//! All the shortest solutions of a puzzle
+//!
+//! A puzzle often has many shortest solutions. Once IDA* has given their
+//! length, a depth-first search bounded by it, backtracking over the moves
+//! as IDA* does, counts the paths reaching the goal in exactly that many
+//! moves. The count of every state is kept by its depth, so a state on many
+//! paths is searched once and the count can be far beyond what enumerating
+//! the paths one by one would allow. Listing the first ones then only
+//! follows the moves leading to at least one of them.
+
+use serde::ser::{SerializeStruct, Serializer};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+use super::grid2d::Direction;
+use super::grid_traits::Grid;
+use super::heuristic::{Evaluator, Heuristic};
+use super::idastar::IdaStar;
+use super::solver::{state_bytes, Limits, Partial, Solver};
+use super::{PuzzleError, Result};
+
+/// Shortest solutions of a puzzle, their number and the first ones
+#[derive(Debug, Clone)]
+pub struct Optimal {
+	/// Moves of each of the solutions
+	pub length: usize,
+	/// Number of distinct move sequences, saturated at `u64::MAX`
+	pub count: u64,
+	/// The first solutions in the order of the moves tried, as many as asked
+	pub solutions: Vec<Vec<Direction>>,
+	/// States expanded, by IDA* and by the count
+	pub time_complexity: usize,
+	/// States whose count was kept
+	pub size_complexity: usize,
+	pub duration: Duration,
+}
+
+impl fmt::Display for Optimal {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "Complexity in time: {}", self.time_complexity)?;
+		writeln!(f, "Complexity in size: {}", self.size_complexity)?;
+		writeln!(f, "Number of moves: {}", self.length)?;
+		writeln!(f, "Shortest solutions: {}", self.count)?;
+		if self.length == 0 {
+			writeln!(f, "Already solved")?;
+		} else {
+			for (i, moves) in self.solutions.iter().enumerate() {
+				writeln!(f, "{:>4}. {}", i + 1, path(moves))?;
+			}
+		}
+		write!(f, "Search time: {:.3}s", self.duration.as_secs_f64())
+	}
+}
+
+/// Solutions as path strings, see the JSON schema in `format`
+impl Serialize for Optimal {
+	fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		let solutions: Vec<String> = self.solutions.iter().map(|moves| path(moves)).collect();
+		let mut s = serializer.serialize_struct("Optimal", 6)?;
+		s.serialize_field("moves", &self.length)?;
+		s.serialize_field("count", &self.count)?;
+		s.serialize_field("solutions", &solutions)?;
+		s.serialize_field("time_complexity", &self.time_complexity)?;
+		s.serialize_field("size_complexity", &self.size_complexity)?;
+		s.serialize_field("search_time_ms", &(self.duration.as_secs_f64() * 1000.0))?;
+		s.end()
+	}
+}
+
+fn path(moves: &[Direction]) -> String {
+	moves.iter().map(|m| m.as_char()).collect()
+}
+
+pub struct OptimalSolutions<G: Grid> {
+	start: G,
+	destination: G,
+	heuristic: Heuristic,
+	evaluator: Evaluator,
+	limits: Limits,
+	timer: Instant,
+	time_complex: usize,
+	length: usize,
+	/// Number of shortest paths to the goal from each state by its depth,
+	/// states cut off by the heuristic are left out
+	counts: Vec<HashMap<G, u64>>,
+	/// A shortest solution, the partial result when stopped by a limit
+	first: Vec<Direction>,
+}
+
+impl<G> OptimalSolutions<G>
+where
+	G: Grid<M = Direction> + Clone + Hash + Eq,
+{
+	pub fn new(start: G, destination: G, heuristic: Heuristic) -> Self {
+		let evaluator = Evaluator::new(heuristic, &destination);
+		Self {
+			start,
+			destination,
+			heuristic,
+			evaluator,
+			limits: Limits::default(),
+			timer: Instant::now(),
+			time_complex: 0,
+			length: 0,
+			counts: Vec::new(),
+			first: Vec::new(),
+		}
+	}
+
+	/// Stops the search with `PuzzleError::Limit` once a limit is exceeded
+	pub fn set_limits(&mut self, limits: Limits) {
+		self.limits = limits;
+	}
+
+	/// Counts the shortest solutions and lists the first `list` of them
+	pub fn solve(&mut self, list: usize) -> Result<Optimal> {
+		self.timer = Instant::now();
+		let mut idastar =
+			IdaStar::new(self.start.clone(), self.destination.clone(), self.heuristic);
+		idastar.set_limits(self.limits);
+		let first = idastar.solve();
+		self.time_complex = idastar.get_time_complexity();
+		self.first = first?.moves;
+		self.length = self.first.len();
+		self.counts = vec![HashMap::new(); self.length];
+		let mut state = self.start.clone();
+		let count = self.count(&mut state, None, 0)?;
+		let mut solutions = Vec::new();
+		self.list(&mut state, &mut Vec::new(), list, &mut solutions)?;
+		Ok(Optimal {
+			length: self.length,
+			count,
+			solutions,
+			time_complexity: self.time_complex,
+			size_complexity: self.size_complex(),
+			duration: self.timer.elapsed(),
+		})
+	}
+
+	fn size_complex(&self) -> usize {
+		self.counts.iter().map(|counts| counts.len()).sum()
+	}
+
+	/// Number of shortest paths to the goal from a state reached in g moves
+	fn count(&mut self, state: &mut G, last: Option<Direction>, g: usize) -> Result<u64> {
+		if g == self.length {
+			return Ok((*state == self.destination) as u64);
+		}
+		if g + self.evaluator.estimate(state) > self.length {
+			return Ok(0);
+		}
+		if let Some(&count) = self.counts[g].get(state) {
+			return Ok(count);
+		}
+		self.time_complex += 1;
+		let memory = self.size_complex() * state_bytes(state);
+		if let Some(limit) = self.limits.exceeded(self.time_complex, memory, &self.timer) {
+			return Err(PuzzleError::Limit(Box::new(Partial {
+				limit,
+				moves: self.first.clone(),
+				estimate: 0,
+				time_complexity: self.time_complex,
+				size_complexity: self.size_complex(),
+				duration: self.timer.elapsed(),
+			})));
+		}
+		// a shortest path never undoes a move, the count does not depend
+		// on the last one
+		let mut count: u64 = 0;
+		for dir in state.possible_moves() {
+			if last == Some(dir.opposite()) {
+				continue;
+			}
+			state.do_move(&dir)?;
+			count = count.saturating_add(self.count(state, Some(dir), g + 1)?);
+			state.undo_move(&dir)?;
+		}
+		self.counts[g].insert(state.clone(), count);
+		Ok(count)
+	}
+
+	/// Appends the shortest paths going through the state, up to `list`
+	fn list(
+		&self,
+		state: &mut G,
+		path: &mut Vec<Direction>,
+		list: usize,
+		solutions: &mut Vec<Vec<Direction>>,
+	) -> Result<()> {
+		if path.len() == self.length {
+			if *state == self.destination && solutions.len() < list {
+				solutions.push(path.clone());
+			}
+			return Ok(());
+		}
+		for dir in state.possible_moves() {
+			if solutions.len() >= list {
+				break;
+			}
+			state.do_move(&dir)?;
+			let g = path.len() + 1;
+			let leads = match g == self.length {
+				true => *state == self.destination,
+				false => self.counts[g].get(state).is_some_and(|&count| count > 0),
+			};
+			if leads {
+				path.push(dir);
+				self.list(state, path, list, solutions)?;
+				path.pop();
+			}
+			state.undo_move(&dir)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::enumerate::DistanceTable;
+	use super::super::grid2d::Grid2D;
+	use super::super::PuzzleType;
+	use super::*;
+	use std::collections::HashSet;
+
+	fn grid(data: Vec<Vec<usize>>) -> Grid2D {
+		let mut grid = Grid2D::new();
+		grid.from_2dvec(data).unwrap();
+		grid
+	}
+
+	#[test]
+	fn all_shortest() {
+		let goal = grid(PuzzleType::Snail.get_template(3, 3));
+		let start = grid(vec![vec![3, 2, 6], vec![1, 4, 0], vec![8, 7, 5]]);
+		let optimal = OptimalSolutions::new(start.clone(), goal.clone(), Heuristic::Manhattan)
+			.solve(usize::MAX)
+			.unwrap();
+		assert!(optimal.count > 1);
+		assert_eq!(optimal.count as usize, optimal.solutions.len());
+		// distinct, each a shortest solution by the distance table
+		let table = DistanceTable::new(&goal).unwrap();
+		assert_eq!(table.distance(&start), Some(optimal.length));
+		let distinct: HashSet<&Vec<Direction>> = optimal.solutions.iter().collect();
+		assert_eq!(optimal.solutions.len(), distinct.len());
+		for moves in optimal.solutions.iter() {
+			let mut state = start.clone();
+			for (i, dir) in moves.iter().enumerate() {
+				state.do_move(dir).unwrap();
+				assert_eq!(table.distance(&state), Some(optimal.length - i - 1));
+			}
+		}
+		// every state one step closer is on one of them
+		let mut firsts = HashSet::new();
+		for dir in start.possible_moves() {
+			let mut next = start.clone();
+			next.do_move(&dir).unwrap();
+			if table.distance(&next) == Some(optimal.length - 1) {
+				firsts.insert(dir);
+			}
+		}
+		let listed: HashSet<Direction> = optimal.solutions.iter().map(|m| m[0]).collect();
+		assert_eq!(firsts, listed);
+		let some = OptimalSolutions::new(start, goal, Heuristic::LinearConflict)
+			.solve(2)
+			.unwrap();
+		assert_eq!((optimal.count, 2), (some.count, some.solutions.len()));
+		assert_eq!(&optimal.solutions[..2], &some.solutions[..]);
+	}
+
+	#[test]
+	fn solved_and_limited() {
+		let goal = grid(PuzzleType::Snail.get_template(3, 3));
+		let solved = OptimalSolutions::new(goal.clone(), goal.clone(), Heuristic::Manhattan)
+			.solve(5)
+			.unwrap();
+		assert_eq!((0, 1), (solved.length, solved.count));
+		let text = solved.to_string();
+		assert!(text.contains("Already solved\n"));
+		assert!(!text.contains("1. "));
+		// stopped while counting, the partial path is a shortest solution
+		let start = grid(vec![vec![3, 2, 6], vec![1, 4, 0], vec![8, 7, 5]]);
+		let mut idastar = IdaStar::new(start.clone(), goal.clone(), Heuristic::Manhattan);
+		let length = idastar.solve().unwrap().len();
+		let mut search = OptimalSolutions::new(start, goal, Heuristic::Manhattan);
+		search.set_limits(Limits {
+			max_nodes: Some(idastar.get_time_complexity()),
+			..Limits::default()
+		});
+		match search.solve(0) {
+			Err(PuzzleError::Limit(partial)) => assert_eq!(length, partial.moves.len()),
+			other => panic!("expected limit, got {:?}", other.map(|o| o.count)),
+		}
+	}
+}
@@ -0,0 +1,356 @@
+//! Shortening of the paths found by the non-optimal solvers
+//!
+//! `optimize` runs three passes over a path, each keeping it a valid path
+//! from the same start to the same goal:
+//!     - moves undone right after are dropped, the constructive solver
+//!       routes the empty tile in a way often doing so
+//!     - a state reached twice means the moves between are a loop, they are
+//!       cut out; states are told apart by a Zobrist hash updated on every
+//!       move, so paths of millions of moves on large boards are fine
+//!     - short windows of the path are solved again optimally by IDA* on the
+//!       part of the board their empty tile goes through, and replaced when
+//!       a shorter sequence exists
+//!
+//! Greedy, weighted and constructive solutions get shorter, an optimal one
+//! stays as it is.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+use super::grid2d::{Direction, Grid2D};
+use super::grid_traits::Grid;
+use super::heuristic::{Evaluator, Heuristic};
+use super::idastar::IdaStar;
+use super::solver::{Limits, Solver};
+use super::{ErrorKind, PuzzleError, Result};
+
+/// Moves of a window solved again
+pub const WINDOW: usize = 12;
+
+/// States expanded at most for a window, it is left as it is beyond
+pub const WINDOW_NODES: usize = 5000;
+
+/// Moves removed by each pass of `optimize`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Savings {
+	pub reversals: usize,
+	pub loops: usize,
+	pub windows: usize,
+}
+
+impl Savings {
+	pub fn total(&self) -> usize {
+		self.reversals + self.loops + self.windows
+	}
+}
+
+impl fmt::Display for Savings {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"Optimized: {} moves saved ({} back-and-forth, {} in loops, {} by re-solving windows)",
+			self.total(),
+			self.reversals,
+			self.loops,
+			self.windows
+		)
+	}
+}
+
+/// Shorter path from the start to the same goal, with the moves saved
+pub fn optimize<G: Grid>(start: &G, moves: &[Direction]) -> Result<(Vec<Direction>, Savings)> {
+	let start = Tiles::new(start);
+	let path = cancel_reversals(moves);
+	let reversals = moves.len() - path.len();
+	let shorter = cut_loops(&start, &path)?;
+	let loops = path.len() - shorter.len();
+	let path = shorten_windows(&start, shorter.clone())?;
+	let savings = Savings {
+		reversals,
+		loops,
+		windows: shorter.len() - path.len(),
+	};
+	Ok((path, savings))
+}
+
+/// Removes the moves undone right after, e.g. "ruld" stays and "rlud" vanishes
+pub fn cancel_reversals(moves: &[Direction]) -> Vec<Direction> {
+	let mut path: Vec<Direction> = Vec::with_capacity(moves.len());
+	for &direction in moves.iter() {
+		match path.last() {
+			Some(&last) if last == direction.opposite() => {
+				path.pop();
+			}
+			_ => path.push(direction),
+		}
+	}
+	path
+}
+
+/// Board as row-major tiles, cheaper to copy and hash than the grids
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Tiles {
+	width: usize,
+	height: usize,
+	tiles: Vec<usize>,
+	blank: usize,
+}
+
+impl Tiles {
+	fn new<G: Grid>(grid: &G) -> Self {
+		let (width, height) = grid.dim();
+		let tiles: Vec<usize> = (0..width * height).map(|i| grid.get_indexed(i)).collect();
+		Self {
+			width,
+			height,
+			blank: tiles.iter().position(|&t| t == 0).unwrap_or(0),
+			tiles,
+		}
+	}
+
+	/// Cell the blank moves to, none off the board
+	fn target(&self, cell: usize, dir: Direction) -> Option<usize> {
+		let (row, col) = (cell / self.width, cell % self.width);
+		match dir {
+			Direction::Up if row > 0 => Some(cell - self.width),
+			Direction::Down if row + 1 < self.height => Some(cell + self.width),
+			Direction::Right if col + 1 < self.width => Some(cell + 1),
+			Direction::Left if col > 0 => Some(cell - 1),
+			_ => None,
+		}
+	}
+
+	/// Moves the blank, returns the hash of the new state
+	fn apply(&mut self, dir: Direction, hash: u64) -> Result<u64> {
+		let to = self
+			.target(self.blank, dir)
+			.ok_or(PuzzleError::Puzzle(ErrorKind::InvalidMove))?;
+		let tile = self.tiles[to];
+		let hash = hash ^ zobrist(self.blank, 0) ^ zobrist(to, tile);
+		self.tiles.swap(self.blank, to);
+		let hash = hash ^ zobrist(self.blank, tile) ^ zobrist(to, 0);
+		self.blank = to;
+		Ok(hash)
+	}
+
+	fn hash(&self) -> u64 {
+		self.tiles
+			.iter()
+			.enumerate()
+			.fold(0, |hash, (cell, &tile)| hash ^ zobrist(cell, tile))
+	}
+
+	/// Optimal replacement of the moves, if shorter
+	///
+	/// The search is confined to the cells the blank goes through and
+	/// their neighbours, renumbered as a small puzzle of its own.
+	fn solve_window(&self, moves: &[Direction]) -> Option<Vec<Direction>> {
+		let (mut top, mut left) = (self.blank / self.width, self.blank % self.width);
+		let (mut bottom, mut right) = (top, left);
+		let mut cell = self.blank;
+		for &dir in moves.iter() {
+			cell = self.target(cell, dir)?;
+			top = top.min(cell / self.width);
+			bottom = bottom.max(cell / self.width);
+			left = left.min(cell % self.width);
+			right = right.max(cell % self.width);
+		}
+		let (top, left) = (top.saturating_sub(1), left.saturating_sub(1));
+		let (bottom, right) = (
+			(bottom + 1).min(self.height - 1),
+			(right + 1).min(self.width - 1),
+		);
+		let cells: Vec<usize> = (top..=bottom)
+			.flat_map(|row| (left..=right).map(move |col| row * self.width + col))
+			.collect();
+		let mut window = Tiles {
+			width: right - left + 1,
+			height: bottom - top + 1,
+			tiles: Vec::with_capacity(cells.len()),
+			blank: 0,
+		};
+		let mut numbers = HashMap::new();
+		for &cell in cells.iter() {
+			let tile = self.tiles[cell];
+			if tile != 0 {
+				numbers.insert(tile, numbers.len() + 1);
+			}
+			window.tiles.push(numbers.get(&tile).copied().unwrap_or(0));
+		}
+		window.blank = window.tiles.iter().position(|&t| t == 0)?;
+		let start = window.grid();
+		for &dir in moves.iter() {
+			window.apply(dir, 0).ok()?;
+		}
+		let goal = window.grid();
+		// a shorter path has the same parity, at least 2 moves less
+		if Evaluator::new(Heuristic::LinearConflict, &goal).estimate(&start) + 2 > moves.len() {
+			return None;
+		}
+		let mut idastar = IdaStar::new(start, goal, Heuristic::LinearConflict);
+		idastar.set_limits(Limits {
+			max_nodes: Some(WINDOW_NODES),
+			..Limits::default()
+		});
+		match idastar.solve() {
+			Ok(solution) if solution.len() < moves.len() => Some(solution.moves),
+			_ => None,
+		}
+	}
+
+	fn grid(&self) -> Grid2D {
+		let mut grid = Grid2D::new();
+		let rows = self.tiles.chunks(self.width).map(|row| row.to_vec());
+		// always valid, the tiles come from a valid board
+		grid.from_2dvec(rows.collect()).unwrap();
+		grid
+	}
+}
+
+/// Pseudo-random key of a tile on a cell, the state hash xors them all
+fn zobrist(cell: usize, tile: usize) -> u64 {
+	// splitmix64 finalizer
+	let mut z = ((cell as u64) << 32 | tile as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+	z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+	z ^ (z >> 31)
+}
+
+/// Removes the moves between two visits of the same state
+fn cut_loops(start: &Tiles, moves: &[Direction]) -> Result<Vec<Direction>> {
+	let mut state = start.clone();
+	let mut hash = state.hash();
+	// hash of the state after each move of the path, and where it was
+	let mut hashes = vec![hash];
+	let mut seen: HashMap<u64, usize> = HashMap::new();
+	seen.insert(hash, 0);
+	let mut path = Vec::with_capacity(moves.len());
+	for &dir in moves.iter() {
+		hash = state.apply(dir, hash)?;
+		path.push(dir);
+		hashes.push(hash);
+		if let Some(&visit) = seen.get(&hash) {
+			// the hashes may collide, undoing the loop has to give the state back
+			let mut previous = state.clone();
+			let mut previous_hash = hash;
+			for &dir in path[visit..].iter().rev() {
+				previous_hash = previous.apply(dir.opposite(), previous_hash)?;
+			}
+			if previous == state {
+				for (index, removed) in hashes.drain(visit + 1..).enumerate() {
+					if seen.get(&removed) == Some(&(visit + 1 + index)) {
+						seen.remove(&removed);
+					}
+				}
+				path.truncate(visit);
+				continue;
+			}
+		}
+		seen.insert(hash, path.len());
+	}
+	Ok(path)
+}
+
+/// Replaces the windows of the path having a shorter solution
+fn shorten_windows(start: &Tiles, mut path: Vec<Direction>) -> Result<Vec<Direction>> {
+	let mut state = start.clone();
+	let mut at = 0;
+	while at + 1 < path.len() {
+		let end = (at + WINDOW).min(path.len());
+		if let Some(shorter) = state.solve_window(&path[at..end]) {
+			// shorter each time, the same window is tried again
+			path.splice(at..end, shorter);
+			continue;
+		}
+		let next = (at + WINDOW / 2).min(path.len());
+		for &dir in path[at..next].iter() {
+			state.apply(dir, 0)?;
+		}
+		at = next;
+	}
+	Ok(path)
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::astar::Astar;
+	use super::super::bench::generate;
+	use super::super::PuzzleType;
+	use super::*;
+
+	fn path(moves: &str) -> Vec<Direction> {
+		moves
+			.chars()
+			.map(|c| Direction::from_char(c).unwrap())
+			.collect()
+	}
+
+	#[test]
+	fn reversals() {
+		assert_eq!(path(""), cancel_reversals(&path("rlud")));
+		assert_eq!(path("ruld"), cancel_reversals(&path("ruld")));
+		assert_eq!(path("u"), cancel_reversals(&path("urldu")));
+		assert_eq!(path("ul"), cancel_reversals(&path("ludrrlul")));
+	}
+
+	#[test]
+	fn shorter_paths() {
+		let job = generate(4, 1, 60, 3, PuzzleType::Snail).pop().unwrap();
+		let board = job.board.unwrap();
+		let (start, goal) = (board.as_grid().unwrap(), board.goal_grid().unwrap());
+		let mut greedy = Astar::new(start.clone(), goal.clone(), Heuristic::Manhattan);
+		greedy.set_weights(0, 1);
+		let found = greedy.solve().unwrap();
+		// a move there and back, then the path found
+		let there = start.possible_moves()[0];
+		let original: Vec<Direction> = vec![there, there.opposite()]
+			.into_iter()
+			.chain(found.moves)
+			.collect();
+		let (optimized, savings) = optimize(&start, &original).unwrap();
+		assert_eq!(original.len() - optimized.len(), savings.total());
+		assert!(savings.reversals >= 2);
+		let mut state = start.clone();
+		for dir in optimized.iter() {
+			state.do_move(dir).unwrap();
+		}
+		assert_eq!(goal, state);
+		let optimal = Astar::new(start.clone(), goal, Heuristic::LinearConflict)
+			.solve()
+			.unwrap();
+		assert!(optimized.len() >= optimal.len());
+		// an optimal path stays as it is
+		let (same, savings) = optimize(&start, &optimal.moves).unwrap();
+		assert_eq!(optimal.moves, same);
+		assert_eq!(0, savings.total());
+	}
+
+	#[test]
+	fn loops() {
+		let job = generate(3, 1, 30, 1, PuzzleType::Snail).pop().unwrap();
+		let start = Tiles::new(&job.board.unwrap().as_grid().unwrap());
+		// the blank goes around a 2x2 square 3 times, giving the start back
+		let square = |a: Direction, b: Direction| vec![a, b, a.opposite(), b.opposite()];
+		let moves: Vec<Direction> = [Direction::Up, Direction::Down]
+			.iter()
+			.flat_map(|&a| {
+				[Direction::Left, Direction::Right]
+					.iter()
+					.map(move |&b| square(a, b))
+			})
+			.find(|moves| {
+				let mut probe = start.clone();
+				moves.iter().all(|&dir| probe.apply(dir, 0).is_ok())
+			})
+			.unwrap()
+			.repeat(3);
+		assert_eq!(12, moves.len());
+		assert!(cut_loops(&start, &moves).unwrap().is_empty());
+		// once around only turns the tiles round
+		assert_eq!(&moves[..5], &cut_loops(&start, &moves[..5]).unwrap()[..]);
+		let mut further = moves.clone();
+		further.push(moves[0]);
+		assert_eq!(&moves[..1], &cut_loops(&start, &further).unwrap()[..]);
+	}
+}
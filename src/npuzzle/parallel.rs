@@ -0,0 +1,444 @@
+//! Parallel iterative deepening A* for sliding puzzle
+//!
+//! The top of the search tree is expanded breadth-first until it holds a
+//! few times more states than there are threads. Each iteration of IDA*
+//! then hands these frontier states out to the worker threads one by one,
+//! a worker searching below its state depth-first within the iteration
+//! bound. As in the sequential IDA*, any solution within the bound is a
+//! shortest one, so the first solution found stops all the workers.
+//!
+//! Meanwhile the calling thread watches the clock and the memory and
+//! reports the progress to the observer.
+
+use std::hash::Hash;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::grid2d::Direction;
+use super::grid_traits::Grid;
+use super::heuristic::{Evaluator, Heuristic};
+use super::solver::{is_solvable, state_bytes, Limits, Partial, Solution, Solver};
+use super::solver::{Observer, Progress, PROGRESS_INTERVAL};
+use super::{ErrorKind, PuzzleError, Result};
+
+/// Frontier states per thread, more of them spread the work more evenly
+const STATES_PER_THREAD: usize = 32;
+/// Deepest frontier, tiny puzzles never get enough states
+const MAX_FRONTIER_DEPTH: usize = 20;
+/// Time between two checks of the running workers
+const WATCH_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Outcome of an iteration or of a single bounded depth-first search
+enum Bound {
+	Found,
+	/// Smallest f above the bound, `usize::MAX` if nothing was cut off
+	Exceeded(usize),
+	/// Stopped by another worker's solution or by a limit
+	Stopped,
+}
+
+/// State reached from the start by given moves
+type Node<G> = (G, Vec<Direction>);
+
+/// What the workers share during a search
+struct Shared {
+	/// Index of the next frontier state to search
+	next: AtomicUsize,
+	/// Expansions of all the workers, added up in batches
+	nodes: AtomicUsize,
+	/// Set once a solution is found or a limit is exceeded
+	stop: AtomicBool,
+	limit: Mutex<Option<ErrorKind>>,
+	solution: Mutex<Option<Vec<Direction>>>,
+	/// Smallest f above the bound of all the workers
+	next_bound: AtomicUsize,
+	/// Lowest h reached and the path to it, h alone is read without locking
+	best_h: AtomicUsize,
+	closest: Mutex<(usize, Vec<Direction>)>,
+	/// Longest path of any worker
+	depth: AtomicUsize,
+}
+
+impl Shared {
+	fn new() -> Self {
+		Self {
+			next: AtomicUsize::new(0),
+			nodes: AtomicUsize::new(0),
+			stop: AtomicBool::new(false),
+			limit: Mutex::new(None),
+			solution: Mutex::new(None),
+			next_bound: AtomicUsize::new(usize::MAX),
+			best_h: AtomicUsize::new(usize::MAX),
+			closest: Mutex::new((usize::MAX, Vec::new())),
+			depth: AtomicUsize::new(0),
+		}
+	}
+
+	/// Stops all the workers, the first limit exceeded is kept
+	fn stop(&self, limit: ErrorKind) {
+		self.limit.lock().unwrap().get_or_insert(limit);
+		self.stop.store(true, Ordering::SeqCst);
+	}
+}
+
+/// One thread's depth-first searches within an iteration
+struct Worker<'s, G> {
+	destination: &'s G,
+	evaluator: &'s Evaluator,
+	shared: &'s Shared,
+	max_nodes: Option<usize>,
+	nodes: usize,
+	/// Expansions not yet added to the shared count
+	unreported: usize,
+}
+
+impl<'s, G> Worker<'s, G>
+where
+	G: Grid<M = Direction> + Clone + Eq,
+{
+	/// Searches frontier states until there are none left, returns the
+	/// number of states expanded
+	fn run(mut self, frontier: &[Node<G>], bound: usize) -> Result<usize> {
+		let mut next = usize::MAX;
+		while !self.shared.stop.load(Ordering::Relaxed) {
+			let i = self.shared.next.fetch_add(1, Ordering::Relaxed);
+			let (mut state, mut path) = match frontier.get(i) {
+				Some((state, path)) => (state.clone(), path.clone()),
+				None => break,
+			};
+			match self.search(&mut state, &mut path, bound)? {
+				Bound::Found => {
+					self.shared.solution.lock().unwrap().get_or_insert(path);
+					self.shared.stop.store(true, Ordering::SeqCst);
+				}
+				Bound::Exceeded(f) => next = next.min(f),
+				Bound::Stopped => break,
+			}
+		}
+		self.report();
+		self.shared.next_bound.fetch_min(next, Ordering::SeqCst);
+		Ok(self.nodes)
+	}
+
+	/// Depth-first search below the bound, the path leads to the state
+	fn search(&mut self, state: &mut G, path: &mut Vec<Direction>, bound: usize) -> Result<Bound> {
+		let h = self.evaluator.estimate(state);
+		if h < self.shared.best_h.load(Ordering::Relaxed) {
+			let mut closest = self.shared.closest.lock().unwrap();
+			if h < closest.0 {
+				*closest = (h, path.clone());
+				self.shared.best_h.store(h, Ordering::Relaxed);
+			}
+		}
+		let f = path.len() + h;
+		if f > bound {
+			return Ok(Bound::Exceeded(f));
+		}
+		if *state == *self.destination {
+			return Ok(Bound::Found);
+		}
+		self.nodes += 1;
+		self.unreported += 1;
+		if self.unreported == PROGRESS_INTERVAL {
+			self.report();
+			self.shared
+				.depth
+				.fetch_max(path.len() + 1, Ordering::Relaxed);
+		}
+		if self.shared.stop.load(Ordering::Relaxed) {
+			return Ok(Bound::Stopped);
+		}
+		let mut next = usize::MAX;
+		for dir in state.possible_moves() {
+			if path.last() == Some(&dir.opposite()) {
+				continue;
+			}
+			state.do_move(&dir)?;
+			path.push(dir);
+			match self.search(state, path, bound)? {
+				Bound::Exceeded(f) => next = next.min(f),
+				found_or_stopped => return Ok(found_or_stopped),
+			}
+			path.pop();
+			state.undo_move(&dir)?;
+		}
+		Ok(Bound::Exceeded(next))
+	}
+
+	/// Adds the expansions to the shared count, checking the node limit
+	fn report(&mut self) {
+		let nodes = self
+			.shared
+			.nodes
+			.fetch_add(self.unreported, Ordering::Relaxed);
+		if self
+			.max_nodes
+			.is_some_and(|max| nodes + self.unreported > max)
+		{
+			self.shared.stop(ErrorKind::NodeLimit);
+		}
+		self.unreported = 0;
+	}
+}
+
+pub struct ParallelIdaStar<G: Grid> {
+	start: G,
+	destination: G,
+	evaluator: Evaluator,
+	threads: usize,
+	time_complex: usize,
+	size_complex: usize,
+	/// States expanded by each worker thread
+	thread_nodes: Vec<usize>,
+	limits: Limits,
+	observer: Option<Box<dyn Observer>>,
+	timer: Instant,
+}
+
+impl<G> ParallelIdaStar<G>
+where
+	G: Grid<M = Direction> + Clone + Hash + Eq + Send + Sync,
+{
+	/// Solver using one thread per CPU core
+	pub fn new(start: G, destination: G, heuristic: Heuristic) -> Self {
+		let evaluator = Evaluator::new(heuristic, &destination);
+		Self {
+			start,
+			destination,
+			evaluator,
+			threads: available_threads(),
+			time_complex: 0,
+			size_complex: 0,
+			thread_nodes: Vec::new(),
+			limits: Limits::default(),
+			observer: None,
+			timer: Instant::now(),
+		}
+	}
+
+	/// 0 for one thread per CPU core
+	pub fn set_threads(&mut self, threads: usize) {
+		self.threads = match threads {
+			0 => available_threads(),
+			threads => threads,
+		};
+	}
+
+	/// Breadth-first levels of the search tree from the start, until the
+	/// last one is big enough to keep all the threads busy
+	///
+	/// The expansion stops at the first level holding the destination, so
+	/// a destination in the frontier is reached by a shortest path.
+	fn frontier(&mut self) -> Result<Vec<Node<G>>> {
+		let target = self.threads * STATES_PER_THREAD;
+		let mut level = vec![(self.start.clone(), Vec::new())];
+		for _ in 0..MAX_FRONTIER_DEPTH {
+			if level.len() >= target || level.iter().any(|(s, _)| *s == self.destination) {
+				break;
+			}
+			let mut next = Vec::new();
+			for (state, path) in level.iter() {
+				self.time_complex += 1;
+				for dir in state.possible_moves() {
+					if path.last() == Some(&dir.opposite()) {
+						continue;
+					}
+					let mut child = state.clone();
+					child.do_move(&dir)?;
+					let mut child_path = path.clone();
+					child_path.push(dir);
+					next.push((child, child_path));
+				}
+			}
+			level = next;
+		}
+		Ok(level)
+	}
+
+	/// Runs the workers over the frontier with given bound
+	fn iterate(&mut self, frontier: &[Node<G>], bound: usize, shared: &Shared) -> Result<Bound> {
+		shared.next.store(0, Ordering::SeqCst);
+		shared.next_bound.store(usize::MAX, Ordering::SeqCst);
+		let threads = self.threads.min(frontier.len());
+		let (destination, evaluator) = (&self.destination, &self.evaluator);
+		let (limits, observer, timer) = (&self.limits, &mut self.observer, &self.timer);
+		let start_nodes = self.time_complex;
+		let bytes = state_bytes(&self.start);
+		let results: Vec<Result<usize>> = thread::scope(|scope| {
+			let workers: Vec<_> = (0..threads)
+				.map(|_| {
+					let worker = Worker {
+						destination,
+						evaluator,
+						shared,
+						max_nodes: limits.max_nodes,
+						nodes: 0,
+						unreported: 0,
+					};
+					scope.spawn(move || worker.run(frontier, bound))
+				})
+				.collect();
+			while !workers.iter().all(|worker| worker.is_finished()) {
+				thread::sleep(WATCH_INTERVAL);
+				let depth = shared.depth.load(Ordering::Relaxed);
+				let memory = (frontier.len() + threads * depth) * bytes;
+				let mut stop = limits.resources_exceeded(memory, timer);
+				if let Some(observer) = observer.as_mut() {
+					let progress = Progress {
+						nodes: start_nodes + shared.nodes.load(Ordering::Relaxed),
+						open: frontier
+							.len()
+							.saturating_sub(shared.next.load(Ordering::Relaxed)),
+						closed: 0,
+						bound,
+						best_h: shared.best_h.load(Ordering::Relaxed),
+						elapsed: timer.elapsed(),
+					};
+					if stop.is_none() && !observer.progress(&progress) {
+						stop = Some(ErrorKind::Interrupted);
+					}
+				}
+				if let Some(limit) = stop {
+					shared.stop(limit);
+				}
+			}
+			workers
+				.into_iter()
+				.map(|worker| worker.join().unwrap())
+				.collect()
+		});
+		self.thread_nodes.resize(self.threads, 0);
+		for (total, nodes) in self.thread_nodes.iter_mut().zip(results) {
+			*total += nodes?;
+		}
+		let depth = shared.depth.load(Ordering::Relaxed);
+		self.size_complex = self.size_complex.max(frontier.len() + threads * depth);
+		if shared.solution.lock().unwrap().is_some() {
+			Ok(Bound::Found)
+		} else if shared.limit.lock().unwrap().is_some() {
+			Ok(Bound::Stopped)
+		} else {
+			Ok(Bound::Exceeded(shared.next_bound.load(Ordering::SeqCst)))
+		}
+	}
+
+	fn solution(&self, moves: Vec<Direction>) -> Solution {
+		Solution {
+			moves,
+			time_complexity: self.time_complex,
+			size_complexity: self.size_complex,
+			duration: self.timer.elapsed(),
+			thread_nodes: self.thread_nodes.clone(),
+		}
+	}
+}
+
+impl<G> Solver for ParallelIdaStar<G>
+where
+	G: Grid<M = Direction> + Clone + Hash + Eq + Send + Sync,
+{
+	/// Finds the shortest path from start to destination
+	fn solve(&mut self) -> Result<Solution> {
+		self.timer = Instant::now();
+		if !is_solvable(&self.start, &self.destination) {
+			return Err(PuzzleError::Puzzle(ErrorKind::Unsolvable));
+		}
+		self.time_complex = 0;
+		self.thread_nodes = vec![0; self.threads];
+		let frontier = self.frontier()?;
+		self.size_complex = frontier.len();
+		if let Some((_, moves)) = frontier.iter().find(|(s, _)| *s == self.destination) {
+			return Ok(self.solution(moves.clone()));
+		}
+		let start_nodes = self.time_complex;
+		let shared = Shared::new();
+		let mut bound = self.evaluator.estimate(&self.start);
+		loop {
+			let outcome = self.iterate(&frontier, bound, &shared)?;
+			self.time_complex = start_nodes + shared.nodes.load(Ordering::SeqCst);
+			match outcome {
+				Bound::Found => {
+					let moves = shared.solution.lock().unwrap().take().unwrap();
+					return Ok(self.solution(moves));
+				}
+				Bound::Stopped => {
+					let (estimate, moves) = shared.closest.lock().unwrap().clone();
+					return Err(PuzzleError::Limit(Box::new(Partial {
+						limit: shared.limit.lock().unwrap().unwrap(),
+						moves,
+						estimate,
+						time_complexity: self.time_complex,
+						size_complexity: self.size_complex,
+						duration: self.timer.elapsed(),
+					})));
+				}
+				Bound::Exceeded(usize::MAX) => {
+					return Err(PuzzleError::Puzzle(ErrorKind::Unsolvable))
+				}
+				Bound::Exceeded(next) => bound = next,
+			}
+		}
+	}
+
+	fn set_limits(&mut self, limits: Limits) {
+		self.limits = limits;
+	}
+
+	fn set_observer(&mut self, observer: Box<dyn Observer>) {
+		self.observer = Some(observer);
+	}
+
+	fn get_time_complexity(&self) -> usize {
+		self.time_complex
+	}
+
+	fn get_size_complexity(&self) -> usize {
+		self.size_complex
+	}
+}
+
+fn available_threads() -> usize {
+	thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::bench::generate;
+	use super::super::idastar::IdaStar;
+	use super::super::PuzzleType;
+	use super::*;
+
+	#[test]
+	fn parallel_search() {
+		let job = generate(4, 1, 60, 5, PuzzleType::Lines00).pop().unwrap();
+		let board = job.board.unwrap();
+		let (start, goal) = (board.as_grid().unwrap(), board.goal_grid().unwrap());
+		let optimal = IdaStar::new(start.clone(), goal.clone(), Heuristic::Manhattan)
+			.solve()
+			.unwrap()
+			.len();
+		for threads in [1, 3].iter() {
+			let mut parallel =
+				ParallelIdaStar::new(start.clone(), goal.clone(), Heuristic::Manhattan);
+			parallel.set_threads(*threads);
+			let solution = parallel.solve().unwrap();
+			assert_eq!(optimal, solution.len());
+			assert_eq!(goal, *solution.states(&start).unwrap().last().unwrap());
+			assert_eq!(*threads, solution.thread_nodes.len());
+			assert!(solution.thread_nodes.iter().sum::<usize>() <= solution.time_complexity);
+		}
+		let mut parallel = ParallelIdaStar::new(goal.clone(), goal.clone(), Heuristic::Manhattan);
+		assert!(parallel.solve().unwrap().is_empty());
+		let mut limited = ParallelIdaStar::new(start, goal, Heuristic::Manhattan);
+		limited.set_limits(Limits {
+			max_nodes: Some(1),
+			..Limits::default()
+		});
+		match limited.solve() {
+			Err(PuzzleError::Limit(partial)) => assert_eq!(ErrorKind::NodeLimit, partial.limit),
+			other => panic!("expected a node limit, got {:?}", other.map(|s| s.len())),
+		}
+	}
+}
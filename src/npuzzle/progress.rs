@@ -0,0 +1,144 @@
+//! Status line of a running search and Ctrl-C handling
+//!
+//! The line is redrawn in place on stderr a few times per second once the
+//! search has run for a while, and only if stderr is a terminal. Ctrl-C sets
+//! a flag the status line passes on to the solver, which then stops with
+//! the statistics and the partial path it got so far. Solutions improved
+//! by an anytime search are printed on their own lines.
+
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::solver::{Observer, Progress, Solution};
+use super::{PuzzleError, Result};
+
+/// Quiet period before the first line, quick searches do not show any
+const DELAY: Duration = Duration::from_millis(500);
+/// Time between two redraws
+const REFRESH: Duration = Duration::from_millis(100);
+
+/// Flag set by Ctrl-C, can be installed only once per process
+pub fn interrupt_flag() -> Result<Arc<AtomicBool>> {
+	let flag = Arc::new(AtomicBool::new(false));
+	let handler_flag = Arc::clone(&flag);
+	ctrlc::set_handler(move || handler_flag.store(true, Ordering::SeqCst))
+		.map_err(|e| PuzzleError::Custom(format!("Cannot handle Ctrl-C: {}", e)))?;
+	Ok(flag)
+}
+
+/// Observer rendering the progress and stopping the search on Ctrl-C
+pub struct StatusLine {
+	interrupted: Arc<AtomicBool>,
+	enabled: bool,
+	last_drawn: Option<Instant>,
+}
+
+impl StatusLine {
+	pub fn new(interrupted: Arc<AtomicBool>) -> Self {
+		Self {
+			interrupted,
+			enabled: io::stderr().is_terminal(),
+			last_drawn: None,
+		}
+	}
+}
+
+impl Observer for StatusLine {
+	fn progress(&mut self, progress: &Progress) -> bool {
+		let due = self.last_drawn.is_none_or(|last| last.elapsed() >= REFRESH);
+		if self.enabled && progress.elapsed >= DELAY && due {
+			// a broken stderr is no reason to stop the search
+			let _ = write!(io::stderr(), "\r\x1b[K{}", status_text(progress));
+			self.last_drawn = Some(Instant::now());
+		}
+		!self.interrupted.load(Ordering::SeqCst)
+	}
+
+	/// Printed whether stderr is a terminal or not, e.g. to a log file
+	fn improved(&mut self, solution: &Solution, bound: f64) {
+		if self.last_drawn.take().is_some() {
+			let _ = write!(io::stderr(), "\r\x1b[K");
+		}
+		let _ = writeln!(io::stderr(), "{}", improved_text(solution, bound));
+	}
+}
+
+/// Clears the line, the search is over once the solver drops its observer
+impl Drop for StatusLine {
+	fn drop(&mut self) {
+		if self.last_drawn.is_some() {
+			let _ = write!(io::stderr(), "\r\x1b[K");
+		}
+	}
+}
+
+/// e.g. "12.5s  4200000 expanded  open 1500000  closed 3900000  f 52  best h 6"
+pub fn status_text(progress: &Progress) -> String {
+	format!(
+		"{:.1}s  {} expanded  open {}  closed {}  f {}  best h {}",
+		progress.elapsed.as_secs_f64(),
+		progress.nodes,
+		progress.open,
+		progress.closed,
+		progress.bound,
+		progress.best_h
+	)
+}
+
+/// e.g. "Found 62 moves in 0.4s, at most 1.50 times the shortest"
+pub fn improved_text(solution: &Solution, bound: f64) -> String {
+	let quality = match bound > 1.0 {
+		true => format!("at most {:.2} times the shortest", bound),
+		false => String::from("the shortest"),
+	};
+	format!(
+		"Found {} moves in {:.1}s, {}",
+		solution.len(),
+		solution.duration.as_secs_f64(),
+		quality
+	)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn status_line() {
+		let progress = Progress {
+			nodes: 4096,
+			open: 1500,
+			closed: 3900,
+			bound: 52,
+			best_h: 6,
+			elapsed: Duration::from_millis(12_500),
+		};
+		assert_eq!(
+			"12.5s  4096 expanded  open 1500  closed 3900  f 52  best h 6",
+			status_text(&progress)
+		);
+		let interrupted = Arc::new(AtomicBool::new(false));
+		let mut line = StatusLine::new(Arc::clone(&interrupted));
+		line.enabled = false;
+		assert!(line.progress(&progress));
+		interrupted.store(true, Ordering::SeqCst);
+		assert!(!line.progress(&progress));
+		let solution = Solution {
+			moves: Vec::new(),
+			time_complexity: 0,
+			size_complexity: 0,
+			duration: Duration::from_millis(400),
+			thread_nodes: Vec::new(),
+		};
+		assert_eq!(
+			"Found 0 moves in 0.4s, at most 1.50 times the shortest",
+			improved_text(&solution, 1.5)
+		);
+		assert_eq!(
+			"Found 0 moves in 0.4s, the shortest",
+			improved_text(&solution, 1.0)
+		);
+	}
+}
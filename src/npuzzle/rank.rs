@@ -0,0 +1,145 @@
+//! Perfect hashing of puzzle states by their Lehmer code
+//!
+//! A state is the permutation of its tiles over the cells, ranking numbers
+//! the n! permutations of n tiles from 0 to n! - 1 in lexicographic order
+//! and unranking gives the permutation back from its number. The digit of
+//! each value is how many smaller values are left after it, read in the
+//! mixed radix n, n - 1, .., 1.
+//!
+//! A pattern follows only some tiles and the blank, e.g. for a pattern
+//! database or a partial goal. Its rank numbers the placements of these
+//! tiles, the n! / (n - k)! sequences of k distinct cells, the same way.
+//!
+//! Ranks are u64, which holds 20!: any board of at most 20 tiles, blank
+//! included, e.g. the 15-puzzle.
+
+/// Largest number of tiles whose permutations a u64 numbers
+pub const MAX_TILES: usize = 20;
+
+/// Number of sequences of k distinct values out of n, n! for k = n
+pub fn permutations(n: usize, k: usize) -> u64 {
+	(n - k + 1..=n).map(|i| i as u64).product()
+}
+
+/// Lexicographic rank of a permutation of 0..n
+///
+/// Example:
+/// ```
+/// # use npuzzle::npuzzle::rank::{rank, unrank};
+///     assert_eq!(0, rank(&[0, 1, 2]));
+///     assert_eq!(5, rank(&[2, 1, 0]));
+///     assert_eq!(vec![1, 2, 0], unrank(3, 3));
+/// ```
+pub fn rank(permutation: &[usize]) -> u64 {
+	rank_partial(permutation, permutation.len())
+}
+
+/// Permutation of 0..n of given rank
+pub fn unrank(rank: u64, n: usize) -> Vec<usize> {
+	unrank_partial(rank, n, n)
+}
+
+/// Rank of a sequence of distinct values of 0..n among all such sequences
+/// of its length
+pub fn rank_partial(values: &[usize], n: usize) -> u64 {
+	values.iter().enumerate().fold(0, |rank, (i, &value)| {
+		let before = values[..i].iter().filter(|&&v| v < value).count();
+		rank * (n - i) as u64 + (value - before) as u64
+	})
+}
+
+/// Sequence of k distinct values of 0..n of given rank
+pub fn unrank_partial(mut rank: u64, n: usize, k: usize) -> Vec<usize> {
+	let mut digits = vec![0; k];
+	for (i, digit) in digits.iter_mut().enumerate().rev() {
+		let base = (n - i) as u64;
+		*digit = (rank % base) as usize;
+		rank /= base;
+	}
+	let mut left: Vec<usize> = (0..n).collect();
+	digits.into_iter().map(|digit| left.remove(digit)).collect()
+}
+
+/// Some tiles of a board and the blank, ranked by the cells they are on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+	cells: usize,
+	/// The blank first, then the tiles in the given order
+	tiles: Vec<usize>,
+}
+
+impl Pattern {
+	/// Pattern of given tiles, the blank is added, on a board of `cells` cells
+	pub fn new(cells: usize, tiles: &[usize]) -> Self {
+		let tiles = std::iter::once(0)
+			.chain(tiles.iter().copied().filter(|&t| t != 0))
+			.collect();
+		Self { cells, tiles }
+	}
+
+	/// The blank then the other tiles of the pattern
+	pub fn tiles(&self) -> &[usize] {
+		&self.tiles
+	}
+
+	/// Number of placements, ranks go from 0 to this one excluded
+	pub fn size(&self) -> u64 {
+		permutations(self.cells, self.tiles.len())
+	}
+
+	/// Rank of the placement of the pattern in a state given as row-major
+	/// tiles, panics if a tile of the pattern is missing
+	pub fn rank(&self, state: &[usize]) -> u64 {
+		let mut cell_of = vec![usize::MAX; state.len()];
+		for (cell, &tile) in state.iter().enumerate() {
+			cell_of[tile] = cell;
+		}
+		let cells: Vec<usize> = self.tiles.iter().map(|&tile| cell_of[tile]).collect();
+		rank_partial(&cells, self.cells)
+	}
+
+	/// Cells of the blank and of the tiles for given rank
+	pub fn unrank(&self, rank: u64) -> Vec<usize> {
+		unrank_partial(rank, self.cells, self.tiles.len())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn ranking() {
+		// lexicographic order of all permutations of 4
+		let all: Vec<Vec<usize>> = (0..24).map(|r| unrank(r, 4)).collect();
+		assert!(all.windows(2).all(|pair| pair[0] < pair[1]));
+		assert!(all.iter().enumerate().all(|(r, p)| rank(p) == r as u64));
+		// the last 15-puzzle, and as many as u64 holds
+		let reversed: Vec<usize> = (0..16).rev().collect();
+		assert_eq!(permutations(16, 16) - 1, rank(&reversed));
+		assert_eq!(reversed, unrank(permutations(16, 16) - 1, 16));
+		let largest = permutations(MAX_TILES, MAX_TILES) - 1;
+		assert_eq!(largest, rank(&unrank(largest, MAX_TILES)));
+		// 3 tiles and the blank of the 8-puzzle
+		let pattern = Pattern::new(9, &[1, 2, 3]);
+		assert_eq!(&[0, 1, 2, 3], pattern.tiles());
+		assert_eq!(9 * 8 * 7 * 6, pattern.size());
+		let mut seen = vec![false; pattern.size() as usize];
+		for r in 0..pattern.size() {
+			let cells = pattern.unrank(r);
+			let mut state = vec![usize::MAX; 9];
+			for (&tile, &cell) in pattern.tiles().iter().zip(cells.iter()) {
+				state[cell] = tile;
+			}
+			// the other tiles anywhere else
+			let free = state.iter_mut().filter(|tile| **tile == usize::MAX);
+			for (tile, other) in free.zip(4..9) {
+				*tile = other;
+			}
+			let r = pattern.rank(&state);
+			assert!(!seen[r as usize]);
+			seen[r as usize] = true;
+		}
+		assert!(seen.iter().all(|&s| s));
+	}
+}
@@ -0,0 +1,143 @@
+//! Replaying move sequences such as "ruldlu" against a puzzle
+//!
+//! Letters name the direction the empty tile moves in, same as in the play
+//! mode and in the solver's output path.
+
+use std::fmt;
+
+use super::grid2d::Direction;
+use super::grid_traits::Grid;
+use super::{ErrorKind, PuzzleError, Result};
+
+/// Parses a move sequence, whitespace and commas are ignored
+pub fn parse_moves(input: &str) -> Result<Vec<Direction>> {
+	let mut moves = Vec::new();
+	for (i, c) in input
+		.chars()
+		.filter(|c| !c.is_whitespace() && *c != ',')
+		.enumerate()
+	{
+		match Direction::from_char(c) {
+			Some(dir) => moves.push(dir),
+			None => {
+				return Err(PuzzleError::Custom(format!(
+					"Invalid move '{}' at index {}",
+					c, i
+				)))
+			}
+		}
+	}
+	Ok(moves)
+}
+
+/// Outcome of a replay
+#[derive(Debug)]
+pub struct Replay<G> {
+	/// Number of moves applied before the end or the first illegal move
+	pub applied: usize,
+	pub total: usize,
+	/// Index and direction of the first illegal move
+	pub illegal: Option<(usize, Direction)>,
+	/// State after the last legal move
+	pub state: G,
+	pub solved: bool,
+}
+
+impl<G> Replay<G>
+where
+	G: Grid<M = Direction> + Clone + PartialEq,
+{
+	/// Applies the moves one by one, stops at the first illegal one
+	pub fn run(start: &G, goal: &G, moves: &[Direction]) -> Self {
+		let mut state = start.clone();
+		let mut illegal = None;
+		for (i, dir) in moves.iter().enumerate() {
+			if state.do_move(dir).is_err() {
+				illegal = Some((i, *dir));
+				break;
+			}
+		}
+		let applied = match illegal {
+			Some((i, _)) => i,
+			None => moves.len(),
+		};
+		let solved = state == *goal;
+		Self {
+			applied,
+			total: moves.len(),
+			illegal,
+			state,
+			solved,
+		}
+	}
+
+	/// All moves were legal and the goal was reached
+	pub fn is_valid_solution(&self) -> bool {
+		self.illegal.is_none() && self.solved
+	}
+
+	/// Error for a replay that is not a valid solution, so that scripts
+	/// can tell from the exit code
+	pub fn check(&self) -> Result<()> {
+		if self.illegal.is_some() {
+			Err(PuzzleError::Puzzle(ErrorKind::IllegalMove))
+		} else if !self.solved {
+			Err(PuzzleError::Custom(String::from(
+				"Replay failed: final state does not match the goal",
+			)))
+		} else {
+			Ok(())
+		}
+	}
+}
+
+impl<G: fmt::Display> fmt::Display for Replay<G> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "Applied {}/{} moves", self.applied, self.total)?;
+		if let Some((i, dir)) = self.illegal {
+			writeln!(f, "Illegal move '{}' at index {}", dir.as_char(), i)?;
+		}
+		writeln!(f, "Final state:\n{}", self.state)?;
+		if self.solved {
+			write!(f, "Final state matches the goal")
+		} else {
+			write!(f, "Final state does not match the goal")
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::grid2d::Grid2D;
+	use super::super::PuzzleType;
+	use super::*;
+
+	#[test]
+	fn replay_moves() {
+		let mut start = Grid2D::new();
+		start
+			.from_2dvec(vec![vec![1, 2, 3], vec![8, 4, 0], vec![7, 6, 5]])
+			.unwrap();
+		let mut goal = Grid2D::new();
+		goal.from_2dvec(PuzzleType::Snail.get_template(3, 3))
+			.unwrap();
+		assert!(parse_moves("lx").is_err());
+		let moves = parse_moves("L, r l").unwrap();
+		assert_eq!(
+			vec![Direction::Left, Direction::Right, Direction::Left],
+			moves
+		);
+		let replay = Replay::run(&start, &goal, &moves);
+		assert!(replay.is_valid_solution());
+		assert!(replay.check().is_ok());
+		let replay = Replay::run(&start, &goal, &parse_moves("rl").unwrap());
+		assert_eq!(Some((0, Direction::Right)), replay.illegal);
+		assert_eq!(0, replay.applied);
+		assert!(!replay.solved);
+		assert_eq!(3, replay.check().unwrap_err().exit_code());
+		let replay = Replay::run(&start, &goal, &parse_moves("lr").unwrap());
+		assert!(replay.illegal.is_none());
+		assert!(!replay.is_valid_solution());
+		assert_eq!(1, replay.check().unwrap_err().exit_code());
+	}
+}
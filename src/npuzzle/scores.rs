@@ -0,0 +1,268 @@
+//! Local high-score table for the play mode
+//!
+//! Every won game is stored as a single line in a plain text file,
+//! so the table survives between sessions and can be inspected by hand.
+
+use chrono::{Local, NaiveDateTime};
+use log::*;
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, prelude::*};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::{Board, ErrorKind, PuzzleError, PuzzleType, Result};
+
+const SCORES_FILE_NAME: &str = ".npuzzle_scores";
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+const HEADER: &str = "# date size goal seed moves optimal time_ms [challenge]";
+
+/// Scores are kept in the home directory, current directory as a fallback
+pub fn default_scores_file() -> String {
+	match env::var("HOME") {
+		Ok(home) => Path::new(&home)
+			.join(SCORES_FILE_NAME)
+			.to_string_lossy()
+			.into_owned(),
+		Err(_) => String::from(SCORES_FILE_NAME),
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Score {
+	pub date: NaiveDateTime,
+	pub size: usize,
+	pub goal: PuzzleType,
+	pub seed: Option<u64>,
+	pub moves: usize,
+	pub optimal: Option<usize>,
+	pub time: Duration,
+	/// Code of the challenge, if the game was one
+	pub challenge: Option<String>,
+}
+
+impl Score {
+	pub fn new(
+		board: &Board,
+		seed: Option<u64>,
+		moves: usize,
+		optimal: Option<usize>,
+		time: Duration,
+	) -> Self {
+		Self {
+			date: Local::now().naive_local(),
+			size: board.get_size().0,
+			goal: board.get_puzzletype(),
+			seed,
+			moves,
+			optimal,
+			time,
+			challenge: None,
+		}
+	}
+
+	/// Ratio of player's moves to the optimal solution length, 1.0 is perfect
+	pub fn efficiency(&self) -> Option<f64> {
+		match self.optimal {
+			Some(0) => Some(1.0),
+			Some(optimal) => Some(self.moves as f64 / optimal as f64),
+			None => None,
+		}
+	}
+}
+
+impl FromStr for Score {
+	type Err = PuzzleError;
+
+	fn from_str(line: &str) -> Result<Self> {
+		let invalid = || PuzzleError::Puzzle(ErrorKind::InvalidInput);
+		let words: Vec<&str> = line.split_whitespace().collect();
+		if words.len() != 7 && words.len() != 8 {
+			return Err(invalid());
+		}
+		let optional = |word: &str| -> Result<Option<u64>> {
+			match word {
+				"-" => Ok(None),
+				_ => word.parse::<u64>().map(Some).map_err(|_| invalid()),
+			}
+		};
+		Ok(Self {
+			date: NaiveDateTime::parse_from_str(words[0], DATE_FORMAT).map_err(|_| invalid())?,
+			size: words[1].parse().map_err(|_| invalid())?,
+			goal: words[2].parse()?,
+			seed: optional(words[3])?,
+			moves: words[4].parse().map_err(|_| invalid())?,
+			optimal: optional(words[5])?.map(|x| x as usize),
+			time: Duration::from_millis(words[6].parse().map_err(|_| invalid())?),
+			challenge: words.get(7).map(|code| String::from(*code)),
+		})
+	}
+}
+
+impl fmt::Display for Score {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let optional = |value: Option<u64>| match value {
+			Some(x) => x.to_string(),
+			None => String::from("-"),
+		};
+		write!(
+			f,
+			"{} {} {} {} {} {} {}",
+			self.date.format(DATE_FORMAT),
+			self.size,
+			self.goal,
+			optional(self.seed),
+			self.moves,
+			optional(self.optimal.map(|x| x as u64)),
+			self.time.as_millis()
+		)?;
+		if let Some(code) = &self.challenge {
+			write!(f, " {}", code)?;
+		}
+		Ok(())
+	}
+}
+
+#[derive(Debug)]
+pub struct ScoreBoard {
+	file: String,
+	scores: Vec<Score>,
+}
+
+impl ScoreBoard {
+	/// Loads the table, missing file is treated as an empty table
+	pub fn load(file: &str) -> Result<Self> {
+		let mut scores = Vec::new();
+		match fs::read_to_string(file) {
+			Ok(contents) => {
+				for (i, line) in contents.lines().enumerate() {
+					let line = line.trim();
+					if line.is_empty() || line.starts_with('#') {
+						continue;
+					}
+					match line.parse::<Score>() {
+						Ok(score) => scores.push(score),
+						Err(_) => warn!("Skipping invalid score on line {} of {}", i + 1, file),
+					}
+				}
+			}
+			Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+			Err(e) => return Err(PuzzleError::Io(e)),
+		}
+		Ok(Self {
+			file: String::from(file),
+			scores,
+		})
+	}
+
+	pub fn save(&self) -> Result<()> {
+		let mut file = File::create(&self.file)?;
+		writeln!(file, "{}", HEADER)?;
+		for score in self.scores.iter() {
+			writeln!(file, "{}", score)?;
+		}
+		Ok(())
+	}
+
+	/// Adds a game to the table and returns its rank among the games of the same size
+	pub fn add(&mut self, score: Score) -> usize {
+		let size = score.size;
+		self.scores.push(score.clone());
+		self.ranked(size).iter().position(|&s| *s == score).unwrap() + 1
+	}
+
+	/// Games of given size, fewest moves first and faster time breaking ties
+	fn ranked(&self, size: usize) -> Vec<&Score> {
+		let mut ranked: Vec<&Score> = self.scores.iter().filter(|s| s.size == size).collect();
+		ranked.sort_by(|a, b| a.moves.cmp(&b.moves).then(a.time.cmp(&b.time)));
+		ranked
+	}
+
+	/// Formatted table of the best `limit` games of given size
+	pub fn leaderboard(&self, size: usize, limit: usize) -> String {
+		let mut out = format!("Leaderboard {}x{}\n", size, size);
+		out.push_str(&format!(
+			"{:>4} {:>6} {:>7} {:>10} {:>9} {:>8} {:>20} {:>19}\n",
+			"#", "moves", "optimal", "efficiency", "time", "goal", "seed", "date"
+		));
+		for (i, score) in self.ranked(size).iter().take(limit).enumerate() {
+			let optimal = match score.optimal {
+				Some(x) => x.to_string(),
+				None => String::from("-"),
+			};
+			let efficiency = match score.efficiency() {
+				Some(x) => format!("{:.2}", x),
+				None => String::from("-"),
+			};
+			let seed = match score.seed {
+				Some(x) => x.to_string(),
+				None => String::from("-"),
+			};
+			out.push_str(&format!(
+				"{:>4} {:>6} {:>7} {:>10} {:>8.1}s {:>8} {:>20} {:>19}\n",
+				i + 1,
+				score.moves,
+				optimal,
+				efficiency,
+				score.time.as_secs_f64(),
+				score.goal,
+				seed,
+				score.date.format(DATE_FORMAT)
+			));
+		}
+		out
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn score_roundtrip() {
+		let line = "2021-06-30T12:00:00 3 snail 42 25 21 15300";
+		let score = line.parse::<Score>().unwrap();
+		assert_eq!(3, score.size);
+		assert_eq!(PuzzleType::Snail, score.goal);
+		assert_eq!(Some(42), score.seed);
+		assert_eq!(Some(21), score.optimal);
+		assert_eq!(line, score.to_string());
+		let unknown = "2021-06-30T12:00:00 4 lines00 - 80 - 1000"
+			.parse::<Score>()
+			.unwrap();
+		assert_eq!(None, unknown.seed);
+		assert_eq!(None, unknown.efficiency());
+		assert!("2021-06-30T12:00:00 3 snail 42".parse::<Score>().is_err());
+		let challenge = "2021-06-30T12:00:00 3 snail 42 25 21 15300 2021-06-30";
+		let score = challenge.parse::<Score>().unwrap();
+		assert_eq!(Some("2021-06-30"), score.challenge.as_deref());
+		assert_eq!(challenge, score.to_string());
+	}
+
+	#[test]
+	fn ranking() {
+		let mut table = ScoreBoard {
+			file: String::new(),
+			scores: Vec::new(),
+		};
+		let score = |size, moves, time| Score {
+			date: NaiveDateTime::parse_from_str("2021-06-30T12:00:00", DATE_FORMAT).unwrap(),
+			size,
+			goal: PuzzleType::Snail,
+			seed: None,
+			moves,
+			optimal: Some(20),
+			time: Duration::from_secs(time),
+			challenge: None,
+		};
+		assert_eq!(1, table.add(score(3, 30, 10)));
+		assert_eq!(1, table.add(score(4, 90, 10)));
+		assert_eq!(1, table.add(score(3, 20, 10)));
+		assert_eq!(3, table.add(score(3, 30, 20)));
+		assert_eq!(Some(1.5), table.scores[0].efficiency());
+		let board = table.leaderboard(3, 10);
+		assert_eq!(5, board.lines().count());
+	}
+}
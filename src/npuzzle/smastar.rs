@@ -0,0 +1,425 @@
+//! Simplified memory-bounded A* (SMA*) for sliding puzzle
+//!
+//! Best-first like A*, but within a budget of states. The successors of an
+//! expanded state are added first, at most 4 over the budget, then the worst
+//! leaves - highest f, shallowest - are forgotten until it is met again. The
+//! parent of a forgotten leaf remembers its f, so that child is generated
+//! again once it looks the most promising. Each f is backed up to the
+//! smallest f of the children, so it only grows and stays a lower bound.
+//! The solution is optimal as long as the budget holds its path, deeper
+//! states get an infinite f.
+//!
+//! States are kept as a tree, like IDA* it does not detect repeated states
+//! apart from a move undoing the previous one.
+
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::hash::Hash;
+use std::time::Instant;
+
+use super::grid2d::Direction;
+use super::grid_traits::Grid;
+use super::heuristic::{Evaluator, Heuristic};
+use super::solver::{is_solvable, state_bytes, Limits, Partial, Solution, Solver};
+use super::solver::{Observer, Progress, PROGRESS_INTERVAL};
+use super::{ErrorKind, PuzzleError, Result};
+
+/// States held by default
+pub const DEFAULT_NODE_BUDGET: usize = 250_000;
+
+/// f of the states the budget cannot hold on a path
+const INFINITE: usize = usize::MAX;
+
+struct Node<G> {
+	state: G,
+	g: usize,
+	/// Backed-up f, never lower than g + h
+	f: usize,
+	parent: Option<usize>,
+	/// Move from the parent
+	dir: Option<Direction>,
+	children: Vec<usize>,
+	/// Moves to the forgotten children with their f
+	forgotten: Vec<(Direction, usize)>,
+	/// In the open set, i.e. not all its successors are held
+	open: bool,
+}
+
+/// Open set order: lowest f first, deeper states first on ties
+type Key = (usize, Reverse<usize>, usize);
+
+pub struct SmaStar<G: Grid> {
+	start: G,
+	destination: G,
+	evaluator: Evaluator,
+	budget: usize,
+	time_complex: usize,
+	size_complex: usize,
+	limits: Limits,
+	observer: Option<Box<dyn Observer>>,
+	/// Tree of the held states, forgotten slots are reused
+	nodes: Vec<Option<Node<G>>>,
+	free: Vec<usize>,
+	held: usize,
+	open: BTreeSet<Key>,
+	/// Lowest h reached and the path to it
+	closest: (usize, Vec<Direction>),
+}
+
+impl<G> SmaStar<G>
+where
+	G: Grid<M = Direction> + Clone + Hash + Eq,
+{
+	pub fn new(start: G, destination: G, heuristic: Heuristic) -> Self {
+		let evaluator = Evaluator::new(heuristic, &destination);
+		Self {
+			start,
+			destination,
+			evaluator,
+			budget: DEFAULT_NODE_BUDGET,
+			time_complex: 0,
+			size_complex: 0,
+			limits: Limits::default(),
+			observer: None,
+			nodes: Vec::new(),
+			free: Vec::new(),
+			held: 0,
+			open: BTreeSet::new(),
+			closest: (usize::MAX, Vec::new()),
+		}
+	}
+
+	/// At least 2 states, the start and one successor
+	pub fn set_node_budget(&mut self, budget: usize) {
+		self.budget = budget.max(2);
+	}
+
+	fn node(&self, index: usize) -> &Node<G> {
+		self.nodes[index].as_ref().unwrap()
+	}
+
+	fn node_mut(&mut self, index: usize) -> &mut Node<G> {
+		self.nodes[index].as_mut().unwrap()
+	}
+
+	fn key(&self, index: usize) -> Key {
+		let node = self.node(index);
+		(node.f, Reverse(node.g), index)
+	}
+
+	/// Moves from the start to given state
+	fn path(&self, mut index: usize) -> Vec<Direction> {
+		let mut moves = Vec::new();
+		while let Some(dir) = self.node(index).dir {
+			moves.push(dir);
+			index = self.node(index).parent.unwrap();
+		}
+		moves.reverse();
+		moves
+	}
+
+	fn insert(&mut self, node: Node<G>) -> usize {
+		self.held += 1;
+		self.size_complex = self.size_complex.max(self.held);
+		match self.free.pop() {
+			Some(index) => {
+				self.nodes[index] = Some(node);
+				index
+			}
+			None => {
+				self.nodes.push(Some(node));
+				self.nodes.len() - 1
+			}
+		}
+	}
+
+	fn set_open(&mut self, index: usize, open: bool) {
+		let key = self.key(index);
+		match open {
+			true => self.open.insert(key),
+			false => self.open.remove(&key),
+		};
+		self.node_mut(index).open = open;
+	}
+
+	/// Changes the f of a state keeping the open set ordered
+	fn set_f(&mut self, index: usize, f: usize) {
+		let open = self.node(index).open;
+		if open {
+			self.set_open(index, false);
+		}
+		self.node_mut(index).f = f;
+		if open {
+			self.set_open(index, true);
+		}
+	}
+
+	/// Generates all the successors of a new state, or the best forgotten
+	/// one of a state expanded before
+	fn expand(&mut self, index: usize) -> Result<()> {
+		self.time_complex += 1;
+		self.set_open(index, false);
+		let node = self.node_mut(index);
+		let (state, g, f) = (node.state.clone(), node.g, node.f);
+		let successors: Vec<(Direction, usize)> = match node.forgotten.is_empty() {
+			true => state
+				.possible_moves()
+				.into_iter()
+				.filter(|&dir| node.dir != Some(dir.opposite()))
+				.map(|dir| (dir, f))
+				.collect(),
+			false => {
+				let (i, _) = node
+					.forgotten
+					.iter()
+					.enumerate()
+					.min_by_key(|(_, &(_, f))| f)
+					.unwrap();
+				vec![node.forgotten.swap_remove(i)]
+			}
+		};
+		for (dir, f) in successors {
+			let mut child = state.clone();
+			child.do_move(&dir)?;
+			let h = self.evaluator.estimate(&child);
+			// a path of g + 1 moves holds g + 2 states
+			let child_f = match g + 2 > self.budget {
+				true => INFINITE,
+				false => f.max(g + 1 + h),
+			};
+			if h < self.closest.0 {
+				let mut moves = self.path(index);
+				moves.push(dir);
+				self.closest = (h, moves);
+			}
+			let child = self.insert(Node {
+				state: child,
+				g: g + 1,
+				f: child_f,
+				parent: Some(index),
+				dir: Some(dir),
+				children: Vec::new(),
+				forgotten: Vec::new(),
+				open: false,
+			});
+			self.set_open(child, true);
+			self.node_mut(index).children.push(child);
+		}
+		if !self.node(index).forgotten.is_empty() {
+			self.set_open(index, true);
+		}
+		if self.node(index).children.is_empty() {
+			// dead end, left to be forgotten
+			self.set_f(index, INFINITE);
+			self.set_open(index, true);
+			if let Some(parent) = self.node(index).parent {
+				self.backup(parent);
+			}
+		} else {
+			self.backup(index);
+		}
+		Ok(())
+	}
+
+	/// Raises the f of the state and its ancestors to the smallest f
+	/// of their successors
+	fn backup(&mut self, mut index: usize) {
+		loop {
+			let node = self.node(index);
+			if node.children.is_empty() && node.forgotten.is_empty() {
+				return;
+			}
+			let lowest = node
+				.children
+				.iter()
+				.map(|&child| self.node(child).f)
+				.chain(node.forgotten.iter().map(|&(_, f)| f))
+				.min()
+				.unwrap();
+			if lowest <= node.f {
+				return;
+			}
+			self.set_f(index, lowest);
+			match self.node(index).parent {
+				Some(parent) => index = parent,
+				None => return,
+			}
+		}
+	}
+
+	/// Forgets the worst leaves until the held states fit the budget
+	fn prune(&mut self) {
+		while self.held > self.budget {
+			let worst = self
+				.open
+				.iter()
+				.rev()
+				.map(|&(_, _, index)| index)
+				.find(|&index| {
+					let node = self.node(index);
+					node.children.is_empty() && node.parent.is_some()
+				});
+			let index = match worst {
+				Some(index) => index,
+				None => return,
+			};
+			self.set_open(index, false);
+			let node = self.nodes[index].take().unwrap();
+			self.free.push(index);
+			self.held -= 1;
+			let parent = node.parent.unwrap();
+			let p = self.node_mut(parent);
+			p.children.retain(|&child| child != index);
+			p.forgotten.push((node.dir.unwrap(), node.f));
+			if !p.open {
+				self.set_open(parent, true);
+			}
+		}
+	}
+
+	/// Checks the limits and reports the progress after an expansion
+	fn check(&mut self, timer: &Instant, best: Key) -> Result<()> {
+		let memory = self.held * state_bytes(&self.start);
+		let mut stop = self.limits.exceeded(self.time_complex, memory, timer);
+		if let Some(observer) = self.observer.as_mut() {
+			if stop.is_none() && self.time_complex.is_multiple_of(PROGRESS_INTERVAL) {
+				let progress = Progress {
+					nodes: self.time_complex,
+					open: self.open.len(),
+					closed: self.held - self.open.len(),
+					bound: best.0,
+					best_h: self.closest.0,
+					elapsed: timer.elapsed(),
+				};
+				if !observer.progress(&progress) {
+					stop = Some(ErrorKind::Interrupted);
+				}
+			}
+		}
+		match stop {
+			Some(limit) => Err(self.partial(limit, timer)),
+			None => Ok(()),
+		}
+	}
+
+	fn partial(&self, limit: ErrorKind, timer: &Instant) -> PuzzleError {
+		PuzzleError::Limit(Box::new(Partial {
+			limit,
+			moves: self.closest.1.clone(),
+			estimate: self.closest.0,
+			time_complexity: self.time_complex,
+			size_complexity: self.size_complex,
+			duration: timer.elapsed(),
+		}))
+	}
+}
+
+impl<G> Solver for SmaStar<G>
+where
+	G: Grid<M = Direction> + Clone + Hash + Eq,
+{
+	/// Finds the shortest path from start to destination, fails with
+	/// `ErrorKind::MemoryLimit` if the budget cannot hold it
+	fn solve(&mut self) -> Result<Solution> {
+		let timer = Instant::now();
+		if !is_solvable(&self.start, &self.destination) {
+			return Err(PuzzleError::Puzzle(ErrorKind::Unsolvable));
+		}
+		self.time_complex = 0;
+		self.size_complex = 0;
+		self.nodes.clear();
+		self.free.clear();
+		self.held = 0;
+		self.open.clear();
+		let h = self.evaluator.estimate(&self.start);
+		self.closest = (h, Vec::new());
+		let root = self.insert(Node {
+			state: self.start.clone(),
+			g: 0,
+			f: h,
+			parent: None,
+			dir: None,
+			children: Vec::new(),
+			forgotten: Vec::new(),
+			open: false,
+		});
+		self.set_open(root, true);
+		loop {
+			let best = match self.open.iter().next() {
+				Some(&key) => key,
+				None => return Err(PuzzleError::Puzzle(ErrorKind::Unsolvable)),
+			};
+			let (f, _, index) = best;
+			if f == INFINITE {
+				return Err(self.partial(ErrorKind::MemoryLimit, &timer));
+			}
+			if self.node(index).state == self.destination {
+				return Ok(Solution {
+					moves: self.path(index),
+					time_complexity: self.time_complex,
+					size_complexity: self.size_complex,
+					duration: timer.elapsed(),
+					thread_nodes: Vec::new(),
+				});
+			}
+			self.expand(index)?;
+			self.prune();
+			self.check(&timer, best)?;
+		}
+	}
+
+	fn set_limits(&mut self, limits: Limits) {
+		self.limits = limits;
+	}
+
+	fn set_observer(&mut self, observer: Box<dyn Observer>) {
+		self.observer = Some(observer);
+	}
+
+	fn get_time_complexity(&self) -> usize {
+		self.time_complex
+	}
+
+	fn get_size_complexity(&self) -> usize {
+		self.size_complex
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::grid2d::Grid2D;
+	use super::super::idastar::IdaStar;
+	use super::super::PuzzleType;
+	use super::*;
+
+	fn grid(data: Vec<Vec<usize>>) -> Grid2D {
+		let mut grid = Grid2D::new();
+		grid.from_2dvec(data).unwrap();
+		grid
+	}
+
+	#[test]
+	fn bounded_memory() {
+		let start = grid(vec![vec![3, 2, 6], vec![1, 4, 0], vec![8, 7, 5]]);
+		let goal = grid(PuzzleType::Snail.get_template(3, 3));
+		let optimal = IdaStar::new(start.clone(), goal.clone(), Heuristic::Manhattan)
+			.solve()
+			.unwrap()
+			.len();
+		for &budget in [40, 200, DEFAULT_NODE_BUDGET].iter() {
+			let mut smastar = SmaStar::new(start.clone(), goal.clone(), Heuristic::Manhattan);
+			smastar.set_node_budget(budget);
+			let solution = smastar.solve().unwrap();
+			assert_eq!(optimal, solution.len(), "budget {}", budget);
+			assert_eq!(goal, *solution.states(&start).unwrap().last().unwrap());
+			// successors are generated before the worst leaves are forgotten
+			assert!(solution.size_complexity <= budget + 4);
+		}
+		let mut smastar = SmaStar::new(start, goal, Heuristic::Manhattan);
+		smastar.set_node_budget(optimal / 2);
+		match smastar.solve() {
+			Err(PuzzleError::Limit(partial)) => assert_eq!(ErrorKind::MemoryLimit, partial.limit),
+			other => panic!("expected a memory limit, got {:?}", other.map(|s| s.len())),
+		}
+	}
+}
@@ -0,0 +1,515 @@
+//! Common parts of all the solvers - the interface, algorithm selection,
+//! solution representation and solvability check
+
+use serde::ser::{SerializeStruct, Serializer};
+use serde::Serialize;
+use std::fmt;
+use std::hash::Hash;
+use std::mem;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use super::anytime::Anytime;
+use super::astar::Astar;
+use super::beam::BeamSearch;
+use super::constructive::Constructive;
+use super::grid2d::Direction;
+use super::grid_traits::Grid;
+use super::heuristic::Heuristic;
+use super::idastar::IdaStar;
+use super::parallel::ParallelIdaStar;
+use super::smastar::SmaStar;
+use super::{ErrorKind, PuzzleError, Result};
+
+/// Search from a start state to a goal state
+pub trait Solver {
+	/// Finds a path from start to goal
+	fn solve(&mut self) -> Result<Solution>;
+
+	/// Stops the search with `PuzzleError::Limit` once a limit is exceeded
+	fn set_limits(&mut self, limits: Limits);
+
+	/// Reports the progress of the search every `PROGRESS_INTERVAL` expansions
+	fn set_observer(&mut self, observer: Box<dyn Observer>);
+
+	/// States expanded by the last search, also after a failed one
+	fn get_time_complexity(&self) -> usize;
+
+	/// Peak number of states held by the last search
+	fn get_size_complexity(&self) -> usize;
+}
+
+/// Expansions between two progress reports
+pub const PROGRESS_INTERVAL: usize = 1024;
+
+/// Snapshot of a running search
+#[derive(Debug, Clone, Copy)]
+pub struct Progress {
+	/// States expanded
+	pub nodes: usize,
+	/// States waiting for expansion, the current depth for IDA*
+	pub open: usize,
+	/// States seen, none are kept by IDA*
+	pub closed: usize,
+	/// f of the state being expanded, the iteration bound for IDA*
+	pub bound: usize,
+	/// Lowest heuristic estimate reached
+	pub best_h: usize,
+	pub elapsed: Duration,
+}
+
+/// Watches a running search, e.g. to render a status line
+pub trait Observer {
+	/// Returning false stops the search with `ErrorKind::Interrupted`
+	fn progress(&mut self, progress: &Progress) -> bool;
+
+	/// Shorter solution found by an anytime search, at most `bound` times
+	/// longer than the shortest one
+	fn improved(&mut self, _solution: &Solution, _bound: f64) {}
+}
+
+/// Resources a search may use, unlimited by default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+	pub timeout: Option<Duration>,
+	/// States expanded
+	pub max_nodes: Option<usize>,
+	/// Estimated bytes held by the search, see `state_bytes`
+	pub max_memory: Option<usize>,
+}
+
+impl Limits {
+	/// Limit exceeded after given number of expansions with given memory held
+	///
+	/// The clock and the memory are only checked every 1024 expansions,
+	/// reading them on every one would be too costly.
+	pub fn exceeded(&self, nodes: usize, memory: usize, timer: &Instant) -> Option<ErrorKind> {
+		if self.max_nodes.is_some_and(|max| nodes > max) {
+			return Some(ErrorKind::NodeLimit);
+		}
+		if !nodes.is_multiple_of(1024) {
+			return None;
+		}
+		self.resources_exceeded(memory, timer)
+	}
+
+	/// Memory or time limit exceeded, checked on every call
+	pub fn resources_exceeded(&self, memory: usize, timer: &Instant) -> Option<ErrorKind> {
+		if self.max_memory.is_some_and(|max| memory > max) {
+			Some(ErrorKind::MemoryLimit)
+		} else if self
+			.timeout
+			.is_some_and(|timeout| timer.elapsed() > timeout)
+		{
+			Some(ErrorKind::Timeout)
+		} else {
+			None
+		}
+	}
+}
+
+/// Settings of the algorithms having their own, given to `Algorithm::solver`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Options {
+	/// Worker threads of parallel IDA*, 0 for one per CPU core
+	pub threads: usize,
+	/// States held by SMA*, `DEFAULT_NODE_BUDGET` if None
+	pub node_budget: Option<usize>,
+	/// States kept at each depth by beam search, `DEFAULT_BEAM_WIDTH` if None
+	pub beam_width: Option<usize>,
+}
+
+/// Estimated bytes taken by a stored state with its bookkeeping
+/// (hash map entry, parent link and queue entry)
+pub fn state_bytes<G: Grid>(state: &G) -> usize {
+	let (width, height) = state.dim();
+	mem::size_of::<G>() + (width * height + height) * mem::size_of::<usize>() + 64
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Algorithm {
+	Astar,
+	IdaStar,
+	Greedy,
+	UniformCost,
+	ParallelIdaStar,
+	SmaStar,
+	Anytime,
+	Constructive,
+	Beam,
+}
+
+impl Algorithm {
+	pub fn as_str_array() -> &'static [&'static str] {
+		&[
+			"astar",
+			"ida-star",
+			"greedy",
+			"uniform-cost",
+			"parallel-ida-star",
+			"sma-star",
+			"anytime",
+			"constructive",
+			"beam",
+		]
+	}
+
+	pub fn as_str(&self) -> &'static str {
+		match *self {
+			Self::Astar => "astar",
+			Self::IdaStar => "ida-star",
+			Self::Greedy => "greedy",
+			Self::UniformCost => "uniform-cost",
+			Self::ParallelIdaStar => "parallel-ida-star",
+			Self::SmaStar => "sma-star",
+			Self::Anytime => "anytime",
+			Self::Constructive => "constructive",
+			Self::Beam => "beam",
+		}
+	}
+
+	/// Whether the solutions are the shortest ones, given an admissible heuristic
+	///
+	/// Anytime search ends with the shortest one only if no limit stops it.
+	pub fn is_optimal(&self) -> bool {
+		!matches!(
+			self,
+			Self::Greedy | Self::Anytime | Self::Constructive | Self::Beam
+		)
+	}
+
+	/// Solver of this algorithm for given puzzle, taking the options it uses
+	pub fn solver<'a, G>(
+		&self,
+		start: G,
+		goal: G,
+		heuristic: Heuristic,
+		options: Options,
+	) -> Box<dyn Solver + 'a>
+	where
+		G: Grid<M = Direction> + Clone + Hash + Eq + Send + Sync + 'a,
+	{
+		match *self {
+			Self::Astar => Box::new(Astar::new(start, goal, heuristic)),
+			Self::IdaStar => Box::new(IdaStar::new(start, goal, heuristic)),
+			Self::Greedy => {
+				let mut astar = Astar::new(start, goal, heuristic);
+				astar.set_weights(0, 1);
+				Box::new(astar)
+			}
+			Self::UniformCost => {
+				let mut astar = Astar::new(start, goal, heuristic);
+				astar.set_weights(1, 0);
+				Box::new(astar)
+			}
+			Self::ParallelIdaStar => {
+				let mut parallel = ParallelIdaStar::new(start, goal, heuristic);
+				parallel.set_threads(options.threads);
+				Box::new(parallel)
+			}
+			Self::SmaStar => {
+				let mut smastar = SmaStar::new(start, goal, heuristic);
+				if let Some(budget) = options.node_budget {
+					smastar.set_node_budget(budget);
+				}
+				Box::new(smastar)
+			}
+			Self::Anytime => Box::new(Anytime::new(start, goal, heuristic)),
+			// no heuristic, the board is solved line by line
+			Self::Constructive => Box::new(Constructive::new(start, goal)),
+			Self::Beam => {
+				let mut beam = BeamSearch::new(start, goal, heuristic);
+				if let Some(width) = options.beam_width {
+					beam.set_beam_width(width);
+				}
+				Box::new(beam)
+			}
+		}
+	}
+}
+
+impl FromStr for Algorithm {
+	type Err = PuzzleError;
+
+	fn from_str(algorithm: &str) -> Result<Self> {
+		match algorithm {
+			"astar" => Ok(Self::Astar),
+			"ida-star" => Ok(Self::IdaStar),
+			"greedy" => Ok(Self::Greedy),
+			"uniform-cost" => Ok(Self::UniformCost),
+			"parallel-ida-star" => Ok(Self::ParallelIdaStar),
+			"sma-star" => Ok(Self::SmaStar),
+			"anytime" => Ok(Self::Anytime),
+			"constructive" => Ok(Self::Constructive),
+			"beam" => Ok(Self::Beam),
+			_ => Err(PuzzleError::Puzzle(ErrorKind::InvalidInput)),
+		}
+	}
+}
+
+impl fmt::Display for Algorithm {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.as_str())
+	}
+}
+
+/// Result of a successful search
+#[derive(Debug, Clone)]
+pub struct Solution {
+	/// Moves of the empty tile from the initial state to the goal
+	pub moves: Vec<Direction>,
+	/// Total number of states ever selected for expansion
+	pub time_complexity: usize,
+	/// Maximum number of states ever represented in memory at the same time
+	pub size_complexity: usize,
+	pub duration: Duration,
+	/// States expanded by each worker thread, empty for sequential searches
+	pub thread_nodes: Vec<usize>,
+}
+
+impl Solution {
+	pub fn len(&self) -> usize {
+		self.moves.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.moves.is_empty()
+	}
+
+	/// Moves in single letter notation, e.g. "ulldr"
+	pub fn path(&self) -> String {
+		self.moves.iter().map(|m| m.as_char()).collect()
+	}
+
+	/// Ordered sequence of states from the initial state to the goal
+	pub fn states<G>(&self, start: &G) -> Result<Vec<G>>
+	where
+		G: Grid<M = Direction> + Clone,
+	{
+		let mut current = start.clone();
+		let mut states = Vec::with_capacity(self.moves.len() + 1);
+		states.push(current.clone());
+		for dir in self.moves.iter() {
+			current.do_move(dir)?;
+			states.push(current.clone());
+		}
+		Ok(states)
+	}
+}
+
+impl fmt::Display for Solution {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "Complexity in time: {}", self.time_complexity)?;
+		writeln!(f, "Complexity in size: {}", self.size_complexity)?;
+		if !self.thread_nodes.is_empty() {
+			let nodes: Vec<String> = self.thread_nodes.iter().map(|n| n.to_string()).collect();
+			writeln!(f, "Expanded per thread: {}", nodes.join(", "))?;
+		}
+		writeln!(f, "Number of moves: {}", self.moves.len())?;
+		writeln!(f, "Path: {}", self.path())?;
+		write!(f, "Search time: {:.3}s", self.duration.as_secs_f64())
+	}
+}
+
+/// Moves as the path string and the statistics, see the JSON schema in `format`
+impl Serialize for Solution {
+	fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		let mut s = serializer.serialize_struct("Solution", 6)?;
+		s.serialize_field("moves", &self.moves.len())?;
+		s.serialize_field("path", &self.path())?;
+		s.serialize_field("time_complexity", &self.time_complexity)?;
+		s.serialize_field("size_complexity", &self.size_complexity)?;
+		s.serialize_field("search_time_ms", &(self.duration.as_secs_f64() * 1000.0))?;
+		match self.thread_nodes.is_empty() {
+			true => s.skip_field("thread_nodes")?,
+			false => s.serialize_field("thread_nodes", &self.thread_nodes)?,
+		}
+		s.end()
+	}
+}
+
+/// What a search stopped by a limit got so far
+#[derive(Debug, Clone)]
+pub struct Partial {
+	/// `ErrorKind::Timeout`, `NodeLimit`, `MemoryLimit`, `BeamExhausted` or
+	/// `Interrupted`
+	pub limit: ErrorKind,
+	/// Moves to the state with the lowest heuristic estimate reached
+	pub moves: Vec<Direction>,
+	/// Heuristic estimate of that state's distance to the goal
+	pub estimate: usize,
+	pub time_complexity: usize,
+	pub size_complexity: usize,
+	pub duration: Duration,
+}
+
+impl Partial {
+	pub fn path(&self) -> String {
+		self.moves.iter().map(|m| m.as_char()).collect()
+	}
+}
+
+impl fmt::Display for Partial {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "{}", self.limit)?;
+		writeln!(f, "Complexity in time: {}", self.time_complexity)?;
+		writeln!(f, "Complexity in size: {}", self.size_complexity)?;
+		writeln!(
+			f,
+			"Closest state: {} moves, estimated {} more",
+			self.moves.len(),
+			self.estimate
+		)?;
+		writeln!(f, "Partial path: {}", self.path())?;
+		write!(f, "Search time: {:.3}s", self.duration.as_secs_f64())
+	}
+}
+
+/// Same fields as `Solution` plus the limit and the estimate
+impl Serialize for Partial {
+	fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		let limit = match self.limit {
+			ErrorKind::NodeLimit => "max-nodes",
+			ErrorKind::MemoryLimit => "max-memory",
+			ErrorKind::BeamExhausted => "beam-width",
+			ErrorKind::Interrupted => "interrupted",
+			_ => "timeout",
+		};
+		let mut s = serializer.serialize_struct("Partial", 7)?;
+		s.serialize_field("limit", limit)?;
+		s.serialize_field("moves", &self.moves.len())?;
+		s.serialize_field("path", &self.path())?;
+		s.serialize_field("estimate", &self.estimate)?;
+		s.serialize_field("time_complexity", &self.time_complexity)?;
+		s.serialize_field("size_complexity", &self.size_complexity)?;
+		s.serialize_field("search_time_ms", &(self.duration.as_secs_f64() * 1000.0))?;
+		s.end()
+	}
+}
+
+/// Parity check of the state against the goal
+///
+/// Every move swaps the empty tile with its neighbour, so it changes
+/// the parity of the permutation and the parity of the empty tile's
+/// Manhattan distance from its goal at the same time. The state is solvable
+/// only if both parities match.
+pub fn is_solvable<G: Grid>(start: &G, goal: &G) -> bool {
+	let (width, height) = start.dim();
+	if goal.dim() != (width, height) {
+		return false;
+	}
+	let size = width * height;
+	let mut goal_index = vec![usize::MAX; size];
+	for i in 0..size {
+		let value = goal.get_indexed(i);
+		if value >= size {
+			return false;
+		}
+		goal_index[value] = i;
+	}
+	// Permutation mapping state positions to goal positions
+	let mut permutation = vec![0; size];
+	let mut zero = (0, 0);
+	for (i, target) in permutation.iter_mut().enumerate() {
+		let value = start.get_indexed(i);
+		if value >= size || goal_index[value] == usize::MAX {
+			return false;
+		}
+		*target = goal_index[value];
+		if value == 0 {
+			zero = (i, goal_index[value]);
+		}
+	}
+	let mut visited = vec![false; size];
+	let mut cycles = 0;
+	for i in 0..size {
+		if !visited[i] {
+			cycles += 1;
+			let mut j = i;
+			while !visited[j] {
+				visited[j] = true;
+				j = permutation[j];
+			}
+		}
+	}
+	let zero_distance =
+		(zero.0 / width).abs_diff(zero.1 / width) + (zero.0 % width).abs_diff(zero.1 % width);
+	(size - cycles) % 2 == zero_distance % 2
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::grid2d::Grid2D;
+	use super::super::heuristic::Evaluator;
+	use super::super::PuzzleType;
+	use super::*;
+
+	fn grid(data: Vec<Vec<usize>>) -> Grid2D {
+		let mut grid = Grid2D::new();
+		grid.from_2dvec(data).unwrap();
+		grid
+	}
+
+	#[test]
+	fn solvability() {
+		let goal = grid(PuzzleType::Snail.get_template(3, 3));
+		// Subject examples
+		let solvable = grid(vec![vec![3, 2, 6], vec![1, 4, 0], vec![8, 7, 5]]);
+		let unsolvable = grid(vec![vec![2, 1, 3], vec![8, 0, 4], vec![7, 6, 5]]);
+		assert!(is_solvable(&goal, &goal));
+		assert!(is_solvable(&solvable, &goal));
+		assert!(!is_solvable(&unsolvable, &goal));
+		let goal = grid(PuzzleType::LinesNN.get_template(4, 4));
+		let mut state = goal.clone();
+		for dir in [Direction::Up, Direction::Left, Direction::Up].iter() {
+			state.do_move(dir).unwrap();
+		}
+		assert!(is_solvable(&state, &goal));
+		let mut swapped = PuzzleType::LinesNN.get_template(4, 4);
+		swapped[0].swap(0, 1);
+		assert!(!is_solvable(&grid(swapped), &goal));
+	}
+
+	#[test]
+	fn limits() {
+		let start = grid(vec![vec![6, 8, 7], vec![2, 5, 4], vec![3, 0, 1]]);
+		let goal = grid(PuzzleType::Snail.get_template(3, 3));
+		let evaluator = Evaluator::new(Heuristic::Manhattan, &goal);
+		let limits = Limits {
+			max_nodes: Some(100),
+			..Limits::default()
+		};
+		for algorithm in [Algorithm::Astar, Algorithm::IdaStar].iter() {
+			let mut solver = algorithm.solver(
+				start.clone(),
+				goal.clone(),
+				Heuristic::Manhattan,
+				Options::default(),
+			);
+			solver.set_limits(limits);
+			match solver.solve() {
+				Err(PuzzleError::Limit(partial)) => {
+					assert_eq!(ErrorKind::NodeLimit, partial.limit);
+					assert_eq!(101, partial.time_complexity);
+					let mut state = start.clone();
+					for dir in partial.moves.iter() {
+						state.do_move(dir).unwrap();
+					}
+					assert_eq!(partial.estimate, evaluator.estimate(&state));
+					assert!(partial.estimate < evaluator.estimate(&start));
+				}
+				other => panic!("expected node limit, got {:?}", other.map(|s| s.len())),
+			}
+		}
+		let limits = Limits {
+			max_memory: Some(1),
+			..Limits::default()
+		};
+		assert_eq!(
+			Some(ErrorKind::MemoryLimit),
+			limits.exceeded(1024, 2, &Instant::now())
+		);
+		assert_eq!(None, limits.exceeded(1023, 2, &Instant::now()));
+	}
+}
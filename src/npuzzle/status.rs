@@ -3,16 +3,44 @@
 use clap;
 use std::cmp::{PartialEq, PartialOrd};
 
+use std::error;
 use std::fmt;
 use std::io;
 
+use super::solver::Partial;
+
 pub type Result<T> = std::result::Result<T, PuzzleError>;
 
+/// Process exit codes, one per error category
+pub mod exit_code {
+	/// Everything went fine
+	pub const SUCCESS: i32 = 0;
+	/// Other failures, e.g. a solution failing verification
+	pub const FAILURE: i32 = 1;
+	/// Invalid command line usage
+	pub const USAGE: i32 = 2;
+	/// Puzzle input could not be parsed or is not a valid state
+	pub const INVALID_INPUT: i32 = 3;
+	/// Invalid move, position or other parameter
+	pub const INVALID_PARAMETER: i32 = 4;
+	/// The puzzle has no solution
+	pub const UNSOLVABLE: i32 = 5;
+	/// Reading or writing a file failed
+	pub const IO: i32 = 6;
+	/// The search was stopped by a limit, e.g. a timeout
+	pub const LIMIT_EXCEEDED: i32 = 7;
+	/// The search was interrupted by Ctrl-C, as for SIGINT by the shell
+	pub const INTERRUPTED: i32 = 130;
+}
+
 #[derive(Debug)]
 pub enum PuzzleError {
 	Io(io::Error),
 	Clap(clap::Error),
 	Puzzle(ErrorKind),
+	Parse(ParseError),
+	/// Search stopped by a limit or interrupted, with what it found so far
+	Limit(Box<Partial>),
 	Custom(String),
 }
 
@@ -22,13 +50,23 @@ pub enum ErrorKind {
 	InvalidNoSize,
 	InvalidChar,
 	InvalidValue,
+	DuplicateValue,
+	MissingValue,
+	InvalidRowLength,
+	TooManyRows,
 	InvalidNoZero,
 	InvalidMode, // should never happen technically
+	IllegalMove,
 	InvalidMove,
 	OutOfBounds,
 	InvalidPoint,
 	EmptyInput,
 	Unsolvable,
+	Timeout,
+	NodeLimit,
+	MemoryLimit,
+	BeamExhausted,
+	Interrupted,
 }
 
 impl ErrorKind {
@@ -37,16 +75,24 @@ impl ErrorKind {
 			ErrorKind::InvalidInput => "Invalid N-puzzle state: General Error",
 			ErrorKind::InvalidNoSize => "Invalid N-puzzle state: No size given",
 			ErrorKind::InvalidChar => "Invalid N-puzzle state: Invalid character",
-			ErrorKind::InvalidValue => {
-				"Invalid N-puzzle state: Invalid value - too high or duplicate"
-			}
+			ErrorKind::InvalidValue => "Invalid N-puzzle state: Invalid value - too high",
+			ErrorKind::DuplicateValue => "Invalid N-puzzle state: Invalid value - duplicate",
+			ErrorKind::MissingValue => "Invalid N-puzzle state: Missing value",
+			ErrorKind::InvalidRowLength => "Invalid N-puzzle state: Row length differs from size",
+			ErrorKind::TooManyRows => "Invalid N-puzzle state: Too many rows",
 			ErrorKind::InvalidNoZero => "Invalid N-puzzle state: Invalid value - zero not found",
 			ErrorKind::InvalidMode => "Invalid program mode",
+			ErrorKind::IllegalMove => "Invalid move sequence: illegal move",
 			ErrorKind::InvalidMove => "Attempted move is invalid",
 			ErrorKind::OutOfBounds => "Position out of bounds",
 			ErrorKind::InvalidPoint => "Position is invalid",
 			ErrorKind::EmptyInput => "Input is not expected to be empty",
 			ErrorKind::Unsolvable => "Given state is unsolvable",
+			ErrorKind::Timeout => "Search limit exceeded: timeout",
+			ErrorKind::NodeLimit => "Search limit exceeded: node budget",
+			ErrorKind::MemoryLimit => "Search limit exceeded: memory cap",
+			ErrorKind::BeamExhausted => "Search failed: the beam ran out of states",
+			ErrorKind::Interrupted => "Search interrupted",
 		}
 	}
 
@@ -56,17 +102,45 @@ impl ErrorKind {
 			| ErrorKind::InvalidInput
 			| ErrorKind::InvalidChar
 			| ErrorKind::InvalidValue
+			| ErrorKind::DuplicateValue
+			| ErrorKind::MissingValue
+			| ErrorKind::InvalidRowLength
+			| ErrorKind::TooManyRows
 			| ErrorKind::InvalidNoZero
-			| ErrorKind::InvalidMode => "InvalidInput",
+			| ErrorKind::InvalidMode
+			| ErrorKind::IllegalMove => "InvalidInput",
 			ErrorKind::InvalidMove
 			| ErrorKind::OutOfBounds
 			| ErrorKind::InvalidPoint
 			| ErrorKind::EmptyInput => "InvalidParameter",
 			ErrorKind::Unsolvable => "Unsolvable",
+			ErrorKind::Timeout
+			| ErrorKind::NodeLimit
+			| ErrorKind::MemoryLimit
+			| ErrorKind::BeamExhausted => "LimitExceeded",
+			ErrorKind::Interrupted => "Interrupted",
+		}
+	}
+
+	/// Exit code of the error's category
+	pub fn exit_code(&self) -> i32 {
+		match self.err_name() {
+			"InvalidInput" => exit_code::INVALID_INPUT,
+			"InvalidParameter" => exit_code::INVALID_PARAMETER,
+			"Unsolvable" => exit_code::UNSOLVABLE,
+			"LimitExceeded" => exit_code::LIMIT_EXCEEDED,
+			"Interrupted" => exit_code::INTERRUPTED,
+			_ => exit_code::FAILURE,
 		}
 	}
 }
 
+impl fmt::Display for ErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.as_str())
+	}
+}
+
 impl fmt::Debug for ErrorKind {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		f.debug_struct(self.err_name())
@@ -80,12 +154,119 @@ impl fmt::Display for PuzzleError {
 		match *self {
 			PuzzleError::Io(ref err) => err.fmt(f),
 			PuzzleError::Clap(ref err) => err.fmt(f),
-			PuzzleError::Puzzle(ref err) => write!(f, "Puzzle Error: {:?}", err),
-			PuzzleError::Custom(ref err) => write!(f, "Custom Error: {:?}", err),
+			PuzzleError::Puzzle(ref err) => err.fmt(f),
+			PuzzleError::Parse(ref err) => err.fmt(f),
+			PuzzleError::Limit(ref partial) => partial.limit.fmt(f),
+			PuzzleError::Custom(ref err) => write!(f, "{}", err),
 		}
 	}
 }
 
+impl error::Error for PuzzleError {
+	fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+		match *self {
+			PuzzleError::Io(ref err) => Some(err),
+			PuzzleError::Clap(ref err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl PuzzleError {
+	/// Documented process exit code, see [`exit_code`]
+	pub fn exit_code(&self) -> i32 {
+		match *self {
+			PuzzleError::Io(_) => exit_code::IO,
+			PuzzleError::Clap(_) => exit_code::USAGE,
+			PuzzleError::Puzzle(ref err) => err.exit_code(),
+			PuzzleError::Parse(ref err) => err.kind.exit_code(),
+			PuzzleError::Limit(ref partial) => partial.limit.exit_code(),
+			PuzzleError::Custom(_) => exit_code::FAILURE,
+		}
+	}
+
+	/// Message for the user, parse and clap errors come already prefixed
+	pub fn report(&self) -> String {
+		match *self {
+			PuzzleError::Parse(_) | PuzzleError::Clap(_) => self.to_string().trim_end().to_string(),
+			_ => format!("error: {}", self),
+		}
+	}
+}
+
+/// Error in the puzzle input with its location
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+	pub kind: ErrorKind,
+	/// Specific reason, e.g. "duplicate tile 5"
+	pub reason: String,
+	/// Source file, if the input came from one
+	pub file: Option<String>,
+	/// 1-based line, 0 if the error has no specific location
+	pub line: usize,
+	/// 1-based column of the offending token
+	pub column: usize,
+	/// Offending token, underlined in the rendered error
+	pub token: String,
+	/// Whole source line of the error
+	pub source_line: String,
+}
+
+impl ParseError {
+	pub fn new(kind: ErrorKind, reason: String) -> Self {
+		Self {
+			kind,
+			reason,
+			file: None,
+			line: 0,
+			column: 0,
+			token: String::new(),
+			source_line: String::new(),
+		}
+	}
+
+	/// Points the error at a token within a source line
+	pub fn at(mut self, line: usize, column: usize, token: &str, source_line: &str) -> Self {
+		self.line = line;
+		self.column = column;
+		self.token = String::from(token);
+		self.source_line = String::from(source_line);
+		self
+	}
+}
+
+/// Compiler-style rendering with the source line underlined
+impl fmt::Display for ParseError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "error: {}", self.reason)?;
+		let file = self.file.as_deref().unwrap_or("<input>");
+		if self.line == 0 {
+			return write!(f, " --> {}", file);
+		}
+		let gutter = " ".repeat(self.line.to_string().len());
+		writeln!(f, "{}--> {}:{}:{}", gutter, file, self.line, self.column)?;
+		writeln!(f, "{} |", gutter)?;
+		writeln!(f, "{} | {}", self.line, self.source_line)?;
+		// Tabs are kept so the marker lines up with the source
+		let padding: String = self
+			.source_line
+			.chars()
+			.take(self.column.saturating_sub(1))
+			.map(|c| if c == '\t' { '\t' } else { ' ' })
+			.collect();
+		let marker = "^".repeat(self.token.chars().count().max(1));
+		write!(f, "{} | {}{}", gutter, padding, marker)
+	}
+}
+
+impl error::Error for ParseError {}
+
+impl From<ParseError> for PuzzleError {
+	fn from(f: ParseError) -> Self {
+		Self::Parse(f)
+	}
+}
+
 impl From<io::Error> for PuzzleError {
 	fn from(f: io::Error) -> Self {
 		Self::Io(f)
@@ -97,3 +278,34 @@ impl From<clap::Error> for PuzzleError {
 		Self::Clap(f)
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use std::error::Error;
+
+	#[test]
+	fn exit_codes() {
+		let err = PuzzleError::from(io::Error::new(io::ErrorKind::NotFound, "no file"));
+		assert_eq!(exit_code::IO, err.exit_code());
+		assert_eq!("no file", err.source().unwrap().to_string());
+		let err = PuzzleError::Puzzle(ErrorKind::TooManyRows);
+		assert_eq!(exit_code::INVALID_INPUT, err.exit_code());
+		assert!(err.source().is_none());
+		assert_eq!("error: Invalid N-puzzle state: Too many rows", err.report());
+		let err = PuzzleError::from(ParseError::new(
+			ErrorKind::DuplicateValue,
+			String::from("duplicate tile 5"),
+		));
+		assert_eq!(exit_code::INVALID_INPUT, err.exit_code());
+		assert_eq!("error: duplicate tile 5\n --> <input>", err.report());
+		assert_eq!(
+			exit_code::INVALID_PARAMETER,
+			PuzzleError::Puzzle(ErrorKind::InvalidMove).exit_code()
+		);
+		assert_eq!(
+			exit_code::UNSOLVABLE,
+			PuzzleError::Puzzle(ErrorKind::Unsolvable).exit_code()
+		);
+	}
+}
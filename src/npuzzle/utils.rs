@@ -4,6 +4,7 @@
 ///
 /// Example:
 /// ```
+/// # use npuzzle::npuzzle::utils::is_in_bounds;
 ///     assert!(is_in_bounds(3, 3, (0,0), (5, 5)))
 ///
 /// ```
@@ -12,6 +13,55 @@ pub fn is_in_bounds<T: PartialOrd>(x: T, y: T, min: (T, T), max: (T, T)) -> bool
 	x >= min.0 && x <= max.0 && y >= min.1 && y <= max.1
 }
 
+/// Aligned text table, the first `left` columns are aligned to the left
+/// and the rest (numbers) to the right
+pub fn format_table(header: &[&str], rows: &[Vec<String>], left: usize) -> String {
+	let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+	for row in rows.iter() {
+		for (width, cell) in widths.iter_mut().zip(row.iter()) {
+			*width = (*width).max(cell.chars().count());
+		}
+	}
+	let line = |cells: Vec<&str>| -> String {
+		cells
+			.iter()
+			.zip(widths.iter())
+			.enumerate()
+			.map(|(i, (cell, &width))| match i < left {
+				true => format!("{:<width$}", cell, width = width),
+				false => format!("{:>width$}", cell, width = width),
+			})
+			.collect::<Vec<String>>()
+			.join("  ")
+			.trim_end()
+			.to_string()
+	};
+	let mut table = line(header.to_vec());
+	for row in rows.iter() {
+		table.push('\n');
+		table.push_str(&line(row.iter().map(|c| c.as_str()).collect()));
+	}
+	table
+}
+
+/// Comma separated values with a header line, cells are quoted if needed
+pub fn format_csv(header: &[&str], rows: &[Vec<String>]) -> String {
+	let mut csv = header.join(",");
+	csv.push('\n');
+	for row in rows.iter() {
+		let cells: Vec<String> = row
+			.iter()
+			.map(|cell| match cell.contains([',', '"']) {
+				true => format!("\"{}\"", cell.replace('"', "\"\"")),
+				false => cell.clone(),
+			})
+			.collect();
+		csv.push_str(&cells.join(","));
+		csv.push('\n');
+	}
+	csv
+}
+
 #[cfg(test)]
 mod test {
 	use super::*;
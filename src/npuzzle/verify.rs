@@ -0,0 +1,182 @@
+//! Independent check of the solver's output - the program must not "cheat"
+//!
+//! Works on plain vectors built from the original input and the goal
+//! template, not on the grid the solver used, and checks that
+//!     - the sequence starts with the original input state
+//!     - every step is a single legal slide of the empty tile
+//!     - every step matches the move reported in the path
+//!     - the last state is the goal of the puzzle type
+//!     - the reported number of moves matches the sequence of states
+
+use super::grid2d::Direction;
+use super::grid_traits::Grid;
+use super::solver::Solution;
+use super::{PuzzleError, PuzzleType, Result};
+
+pub struct Verifier {
+	width: usize,
+	height: usize,
+	start: Vec<usize>,
+	goal: Vec<usize>,
+}
+
+impl Verifier {
+	pub fn new(width: usize, height: usize, start: &[usize], puzzletype: PuzzleType) -> Self {
+		Self {
+			width,
+			height,
+			start: start.to_vec(),
+			goal: puzzletype.get_template(width, height).concat(),
+		}
+	}
+
+	/// Verifies the solution and the emitted sequence of states
+	pub fn verify<G: Grid>(&self, solution: &Solution, states: &[G]) -> Result<()> {
+		let states: Vec<Vec<usize>> = states.iter().map(|s| self.flatten(s)).collect();
+		if states.len() != solution.len() + 1 {
+			return Err(fail(format!(
+				"reported {} moves but the sequence has {} states",
+				solution.len(),
+				states.len()
+			)));
+		}
+		if states[0] != self.start {
+			return Err(fail(String::from("first state is not the input state")));
+		}
+		let mut replayed = self.start.clone();
+		for (i, dir) in solution.moves.iter().enumerate() {
+			self.check_slide(&states[i], &states[i + 1])
+				.map_err(|e| fail(format!("step {}: {}", i + 1, e)))?;
+			self.slide(&mut replayed, *dir)
+				.map_err(|e| fail(format!("move {}: {}", i + 1, e)))?;
+			if replayed != states[i + 1] {
+				return Err(fail(format!(
+					"move {} '{}' does not lead to the reported state",
+					i + 1,
+					dir.as_char()
+				)));
+			}
+		}
+		if replayed != self.goal {
+			return Err(fail(String::from("last state is not the goal")));
+		}
+		Ok(())
+	}
+
+	/// Verifies a bare move sequence, e.g. one produced by another program
+	pub fn verify_moves(&self, moves: &[Direction]) -> Result<()> {
+		let mut replayed = self.start.clone();
+		for (i, dir) in moves.iter().enumerate() {
+			self.slide(&mut replayed, *dir)
+				.map_err(|e| fail(format!("move {}: {}", i + 1, e)))?;
+		}
+		if replayed != self.goal {
+			return Err(fail(String::from("last state is not the goal")));
+		}
+		Ok(())
+	}
+
+	fn flatten<G: Grid>(&self, grid: &G) -> Vec<usize> {
+		if grid.dim() != (self.width, self.height) {
+			return Vec::new();
+		}
+		(0..self.width * self.height)
+			.map(|i| grid.get_indexed(i))
+			.collect()
+	}
+
+	/// Two states differ exactly by the empty tile swapped with its neighbour
+	fn check_slide(&self, from: &[usize], to: &[usize]) -> std::result::Result<(), String> {
+		if from.len() != self.start.len() || to.len() != self.start.len() {
+			return Err(String::from("state has wrong dimensions"));
+		}
+		let a = zero_index(from)?;
+		let b = zero_index(to)?;
+		if !self.adjacent(a, b) {
+			return Err(String::from("empty tile did not move to a neighbour"));
+		}
+		let mut expected = from.to_vec();
+		expected.swap(a, b);
+		if expected != to {
+			return Err(String::from("tiles other than the slid one changed"));
+		}
+		Ok(())
+	}
+
+	fn slide(&self, state: &mut [usize], dir: Direction) -> std::result::Result<(), String> {
+		let zero = zero_index(state)?;
+		let (row, col) = (zero / self.width, zero % self.width);
+		let target = match dir {
+			Direction::Up if row > 0 => zero - self.width,
+			Direction::Down if row + 1 < self.height => zero + self.width,
+			Direction::Left if col > 0 => zero - 1,
+			Direction::Right if col + 1 < self.width => zero + 1,
+			_ => return Err(format!("'{}' moves out of the board", dir.as_char())),
+		};
+		state.swap(zero, target);
+		Ok(())
+	}
+
+	fn adjacent(&self, a: usize, b: usize) -> bool {
+		let (ra, ca) = (a / self.width, a % self.width);
+		let (rb, cb) = (b / self.width, b % self.width);
+		ra.abs_diff(rb) + ca.abs_diff(cb) == 1
+	}
+}
+
+fn zero_index(state: &[usize]) -> std::result::Result<usize, String> {
+	let mut zeros = state.iter().enumerate().filter(|(_, &v)| v == 0);
+	match (zeros.next(), zeros.next()) {
+		(Some((i, _)), None) => Ok(i),
+		_ => Err(String::from("state must contain exactly one empty tile")),
+	}
+}
+
+fn fail(reason: String) -> PuzzleError {
+	PuzzleError::Custom(format!("Solution verification failed: {}", reason))
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::grid2d::Grid2D;
+	use super::super::{Astar, Heuristic, Solver};
+	use super::*;
+	use std::time::Duration;
+
+	#[test]
+	fn catches_cheating() {
+		let input = vec![3, 2, 6, 1, 4, 0, 8, 7, 5];
+		let mut start = Grid2D::new();
+		start
+			.from_2dvec(input.chunks(3).map(|r| r.to_vec()).collect())
+			.unwrap();
+		let mut goal = Grid2D::new();
+		goal.from_2dvec(PuzzleType::Snail.get_template(3, 3))
+			.unwrap();
+		let verifier = Verifier::new(3, 3, &input, PuzzleType::Snail);
+		let solution = Astar::new(start.clone(), goal.clone(), Heuristic::Manhattan)
+			.solve()
+			.unwrap();
+		let states = solution.states(&start).unwrap();
+		assert!(verifier.verify(&solution, &states).is_ok());
+		// teleporting straight to the goal
+		let mut cheat = solution.clone();
+		cheat.moves.truncate(1);
+		assert!(verifier
+			.verify(&cheat, &[start.clone(), goal.clone()])
+			.is_err());
+		// wrong move count
+		assert!(verifier.verify(&cheat, &states).is_err());
+		// not reaching the goal
+		let short = Solution {
+			moves: Vec::new(),
+			time_complexity: 0,
+			size_complexity: 0,
+			duration: Duration::from_secs(0),
+			thread_nodes: Vec::new(),
+		};
+		assert!(verifier.verify(&short, &[start]).is_err());
+		assert!(verifier.verify_moves(&solution.moves).is_ok());
+		assert!(verifier.verify_moves(&solution.moves[1..]).is_err());
+	}
+}
@@ -0,0 +1,91 @@
+//! Korf's 100 fifteen-puzzle instances solved by the strongest solver,
+//! every solution has to be of the known optimal length
+//!
+//! The whole set takes hours, only a few quick instances are solved by
+//! default. KORF_INSTANCES selects others by their numbers and ranges,
+//! e.g. "1-10,42", or "all":
+//!
+//!     KORF_INSTANCES=1-10 cargo test --release --test korf100
+
+use std::env;
+use std::fs;
+use std::ops::RangeInclusive;
+
+use npuzzle::npuzzle::format::Format;
+use npuzzle::npuzzle::solver::Options;
+use npuzzle::npuzzle::{Algorithm, Board, Heuristic, PuzzleType};
+
+const CORPUS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/korf100/korf100");
+
+/// Instances solved in a fraction of a second even in debug builds
+const DEFAULT_INSTANCES: &str = "55,79";
+
+/// Instance numbers given as "all" or a list such as "1-10,42"
+fn selected(spec: &str) -> Vec<usize> {
+	if spec.trim() == "all" {
+		return (1..=100).collect();
+	}
+	let number = |n: &str| -> usize {
+		match n.trim().parse() {
+			Ok(n) if (1..=100).contains(&n) => n,
+			_ => panic!("KORF_INSTANCES: invalid instance number {:?}", n),
+		}
+	};
+	spec.split(',')
+		.flat_map(|part| -> RangeInclusive<usize> {
+			match part.split_once('-') {
+				Some((first, last)) => number(first)..=number(last),
+				None => number(part)..=number(part),
+			}
+		})
+		.collect()
+}
+
+/// Optimal lengths from the comments, e.g. "# 1: 57"
+fn optimal_lengths() -> Vec<usize> {
+	fs::read_to_string(CORPUS)
+		.unwrap()
+		.lines()
+		.filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+		.map(|line| {
+			let comment = line.split('#').nth(1).unwrap();
+			comment.split(':').nth(1).unwrap().trim().parse().unwrap()
+		})
+		.collect()
+}
+
+#[test]
+fn korf100() {
+	let boards: Vec<Board> = Board::all_from_file(CORPUS, Format::Text)
+		.unwrap()
+		.into_iter()
+		.map(|board| {
+			let mut board = board.unwrap();
+			board.set_puzzletype(PuzzleType::Lines00);
+			board
+		})
+		.collect();
+	let lengths = optimal_lengths();
+	assert_eq!(100, boards.len());
+	assert_eq!(100, lengths.len());
+	let spec = env::var("KORF_INSTANCES").unwrap_or_else(|_| String::from(DEFAULT_INSTANCES));
+	for instance in selected(&spec) {
+		let board = &boards[instance - 1];
+		let (start, goal) = (board.as_grid().unwrap(), board.goal_grid().unwrap());
+		let mut solver = Algorithm::IdaStar.solver(
+			start.clone(),
+			goal.clone(),
+			Heuristic::LinearConflict,
+			Options::default(),
+		);
+		let solution = solver.solve().unwrap();
+		let states = solution.states(&start).unwrap();
+		assert_eq!(goal, *states.last().unwrap(), "instance {}", instance);
+		assert_eq!(
+			lengths[instance - 1],
+			solution.len(),
+			"instance {}",
+			instance
+		);
+	}
+}